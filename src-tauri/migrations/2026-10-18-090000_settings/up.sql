@@ -0,0 +1,4 @@
+CREATE TABLE settings (
+    key TEXT NOT NULL PRIMARY KEY,      -- setting name, e.g. "backend"
+    value TEXT NOT NULL
+);
@@ -0,0 +1,2 @@
+DROP TABLE rotation_played;
+DROP TABLE rotations;
@@ -0,0 +1,17 @@
+CREATE TABLE rotations (
+    screen TEXT NOT NULL PRIMARY KEY,           -- screen identifier, or "all"
+    interval_secs INTEGER NOT NULL,
+    wallpaper_source_id TEXT REFERENCES wallpaper_sources(id) ON DELETE CASCADE, -- NULL = every active source
+    favorites_only BOOLEAN NOT NULL DEFAULT FALSE,
+    shuffle BOOLEAN NOT NULL DEFAULT TRUE,      -- FALSE = sequential by path
+    mode TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_changed_at BIGINT NOT NULL DEFAULT 0   -- unix timestamp
+);
+
+-- wallpapers already shown in the current cycle, cleared once the pool is exhausted
+CREATE TABLE rotation_played (
+    screen TEXT NOT NULL REFERENCES rotations(screen) ON DELETE CASCADE,
+    wallpaper_id TEXT NOT NULL REFERENCES wallpapers(id) ON DELETE CASCADE,
+    PRIMARY KEY (screen, wallpaper_id)
+);
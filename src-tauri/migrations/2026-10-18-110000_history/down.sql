@@ -0,0 +1,2 @@
+DROP TABLE history_cursor;
+DROP TABLE history;
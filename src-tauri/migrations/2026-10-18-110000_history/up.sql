@@ -0,0 +1,16 @@
+CREATE TABLE history (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    screen TEXT NOT NULL,                       -- screen identifier, never "all"
+    wallpaper_id TEXT NOT NULL REFERENCES wallpapers(id) ON DELETE CASCADE,
+    mode TEXT NOT NULL,
+    origin TEXT NOT NULL,                       -- "manual", "random", "rotation" or "restore"
+    created_at BIGINT NOT NULL                  -- unix timestamp
+);
+
+CREATE INDEX history_screen_idx ON history(screen, id);
+
+-- entry shown after going back or forward, no row means the newest entry
+CREATE TABLE history_cursor (
+    screen TEXT NOT NULL PRIMARY KEY,
+    history_id INTEGER NOT NULL REFERENCES history(id) ON DELETE CASCADE
+);
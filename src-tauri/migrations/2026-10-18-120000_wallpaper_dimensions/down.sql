@@ -0,0 +1,6 @@
+DROP INDEX wallpapers_dimensions_idx;
+ALTER TABLE wallpapers DROP COLUMN mtime;
+ALTER TABLE wallpapers DROP COLUMN file_size;
+ALTER TABLE wallpapers DROP COLUMN aspect_ratio;
+ALTER TABLE wallpapers DROP COLUMN height;
+ALTER TABLE wallpapers DROP COLUMN width;
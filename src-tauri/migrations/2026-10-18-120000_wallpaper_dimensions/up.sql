@@ -0,0 +1,8 @@
+-- read from the image headers during the scan, NULL for rows scanned before this migration
+ALTER TABLE wallpapers ADD COLUMN width INTEGER;
+ALTER TABLE wallpapers ADD COLUMN height INTEGER;
+ALTER TABLE wallpapers ADD COLUMN aspect_ratio DOUBLE; -- width / height
+ALTER TABLE wallpapers ADD COLUMN file_size BIGINT;    -- bytes
+ALTER TABLE wallpapers ADD COLUMN mtime BIGINT;        -- unix timestamp
+
+CREATE INDEX wallpapers_dimensions_idx ON wallpapers(width, height);
@@ -0,0 +1,3 @@
+DROP INDEX duplicates_wallpaper_idx;
+DROP TABLE duplicates;
+ALTER TABLE wallpapers DROP COLUMN phash;
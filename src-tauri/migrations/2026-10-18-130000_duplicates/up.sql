@@ -0,0 +1,13 @@
+-- 64 bit difference hash of the decoded image, filled in by the thumbnail pass
+ALTER TABLE wallpapers ADD COLUMN phash BIGINT;
+
+-- files with the same content as a wallpaper, which only keeps one row per signature
+CREATE TABLE duplicates (
+    path TEXT NOT NULL PRIMARY KEY,
+    wallpaper_id TEXT NOT NULL REFERENCES wallpapers(id) ON DELETE CASCADE,
+    wallpaper_source_id TEXT NOT NULL REFERENCES wallpaper_sources(id) ON DELETE CASCADE,
+    file_size BIGINT,   -- bytes
+    mtime BIGINT        -- unix timestamp
+);
+
+CREATE INDEX duplicates_wallpaper_idx ON duplicates(wallpaper_id);
@@ -0,0 +1,2 @@
+-- the original mode strings are gone, nothing to restore
+SELECT 1;
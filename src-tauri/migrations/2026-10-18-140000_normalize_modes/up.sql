@@ -0,0 +1,7 @@
+-- unknown modes used to fall back to default silently, they are rejected now
+UPDATE active SET mode = 'default'
+    WHERE lower(mode) NOT IN ('default', 'contain', 'tile', 'span');
+UPDATE rotations SET mode = 'default'
+    WHERE lower(mode) NOT IN ('default', 'contain', 'tile', 'span');
+UPDATE history SET mode = 'default'
+    WHERE lower(mode) NOT IN ('default', 'contain', 'tile', 'span');
@@ -0,0 +1 @@
+ALTER TABLE wallpapers DROP COLUMN palette;
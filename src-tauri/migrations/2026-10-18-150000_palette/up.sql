@@ -0,0 +1,2 @@
+-- comma separated #rrggbb colors, most dominant first, filled in by the thumbnail pass
+ALTER TABLE wallpapers ADD COLUMN palette TEXT;
@@ -0,0 +1,23 @@
+-- SQLite can't drop a column with a foreign key, the table is rebuilt without it. Rotations
+-- of a collection are kept and pick from every active source again
+CREATE TABLE rotations_old (
+    screen TEXT NOT NULL PRIMARY KEY,
+    interval_secs INTEGER NOT NULL,
+    wallpaper_source_id TEXT REFERENCES wallpaper_sources(id) ON DELETE CASCADE,
+    favorites_only BOOLEAN NOT NULL DEFAULT FALSE,
+    shuffle BOOLEAN NOT NULL DEFAULT TRUE,
+    mode TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_changed_at BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO rotations_old
+SELECT screen, interval_secs, wallpaper_source_id, favorites_only, shuffle, mode, enabled, last_changed_at
+FROM rotations;
+
+DROP TABLE rotations;
+ALTER TABLE rotations_old RENAME TO rotations;
+
+DROP INDEX collection_wallpapers_wallpaper_idx;
+DROP TABLE collection_wallpapers;
+DROP TABLE collections;
@@ -0,0 +1,18 @@
+-- named, user ordered lists of wallpapers
+CREATE TABLE collections (
+    id TEXT NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    created_at BIGINT NOT NULL  -- unix timestamp
+);
+
+CREATE TABLE collection_wallpapers (
+    collection_id TEXT NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+    wallpaper_id TEXT NOT NULL REFERENCES wallpapers(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,  -- 0 based, may have gaps after wallpapers were removed by a scan
+    PRIMARY KEY (collection_id, wallpaper_id)
+);
+
+CREATE INDEX collection_wallpapers_wallpaper_idx ON collection_wallpapers(wallpaper_id);
+
+-- NULL = every active source, which rotations of a deleted collection fall back to
+ALTER TABLE rotations ADD COLUMN collection_id TEXT REFERENCES collections(id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+DROP INDEX wallpaper_tags_tag_idx;
+DROP TABLE wallpaper_tags;
+DROP TABLE tags;
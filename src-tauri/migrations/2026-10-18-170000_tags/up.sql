@@ -0,0 +1,57 @@
+-- lowercase, without whitespace, commas or parentheses
+CREATE TABLE tags (
+    id INTEGER NOT NULL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE wallpaper_tags (
+    wallpaper_id TEXT NOT NULL REFERENCES wallpapers(id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    origin TEXT NOT NULL,  -- filename, metadata or user
+    PRIMARY KEY (wallpaper_id, tag_id)
+);
+
+CREATE INDEX wallpaper_tags_tag_idx ON wallpaper_tags(tag_id);
+
+-- Keywords were built from the file name unless image_metadata.json had tags for the file.
+-- The file name is what follows the last '/', its stem what precedes the last '.'
+CREATE TEMPORARY TABLE keyword_words AS
+WITH RECURSIVE
+    names(wallpaper_id, keywords, name) AS (
+        SELECT id, keywords, substr(path, length(rtrim(path, replace(path, '/', ''))) + 1)
+        FROM wallpapers
+        WHERE keywords IS NOT NULL
+    ),
+    stems(wallpaper_id, keywords, stem) AS (
+        SELECT wallpaper_id, keywords, CASE
+            WHEN instr(name, '.') = 0 THEN name
+            ELSE substr(name, 1, length(rtrim(name, replace(name, '.', ''))) - 1)
+        END
+        FROM names
+    ),
+    split(wallpaper_id, origin, word, rest) AS (
+        SELECT
+            wallpaper_id,
+            CASE
+                WHEN keywords = replace(replace(stem, '_', ' '), '-', ' ') THEN 'filename'
+                ELSE 'metadata'
+            END,
+            '',
+            lower(replace(replace(replace(replace(replace(replace(
+                keywords, ',', ' '), '(', ' '), ')', ' '), char(9), ' '), char(10), ' '), char(13), ' ')) || ' '
+        FROM stems
+        UNION ALL
+        SELECT wallpaper_id, origin, substr(rest, 1, instr(rest, ' ') - 1), substr(rest, instr(rest, ' ') + 1)
+        FROM split
+        WHERE rest <> ''
+    )
+SELECT DISTINCT wallpaper_id, origin, word FROM split WHERE word <> '';
+
+INSERT OR IGNORE INTO tags (name) SELECT word FROM keyword_words;
+
+INSERT OR IGNORE INTO wallpaper_tags (wallpaper_id, tag_id, origin)
+SELECT k.wallpaper_id, t.id, k.origin
+FROM keyword_words k
+INNER JOIN tags t ON t.name = k.word;
+
+DROP TABLE keyword_words;
@@ -0,0 +1,4 @@
+DROP TRIGGER wallpapers_fts_delete;
+DROP TRIGGER wallpapers_fts_update;
+DROP TRIGGER wallpapers_fts_insert;
+DROP TABLE wallpapers_fts;
@@ -0,0 +1,35 @@
+-- full text index of the wallpapers, kept in sync by the triggers below
+-- file_name is what follows the last '/' of the path
+CREATE VIRTUAL TABLE wallpapers_fts USING fts5(
+    wallpaper_id UNINDEXED,
+    keywords,
+    file_name,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name)
+SELECT id, coalesce(keywords, ''), substr(path, length(rtrim(path, replace(path, '/', ''))) + 1)
+FROM wallpapers;
+
+CREATE TRIGGER wallpapers_fts_insert AFTER INSERT ON wallpapers BEGIN
+    INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name)
+    VALUES (
+        new.id,
+        coalesce(new.keywords, ''),
+        substr(new.path, length(rtrim(new.path, replace(new.path, '/', ''))) + 1)
+    );
+END;
+
+CREATE TRIGGER wallpapers_fts_update AFTER UPDATE OF path, keywords ON wallpapers BEGIN
+    DELETE FROM wallpapers_fts WHERE wallpaper_id = old.id;
+    INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name)
+    VALUES (
+        new.id,
+        coalesce(new.keywords, ''),
+        substr(new.path, length(rtrim(new.path, replace(new.path, '/', ''))) + 1)
+    );
+END;
+
+CREATE TRIGGER wallpapers_fts_delete AFTER DELETE ON wallpapers BEGIN
+    DELETE FROM wallpapers_fts WHERE wallpaper_id = old.id;
+END;
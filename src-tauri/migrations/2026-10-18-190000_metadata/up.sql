@@ -0,0 +1,55 @@
+-- filled in from the metadata file of the source by full scans
+ALTER TABLE wallpapers ADD COLUMN caption TEXT;
+ALTER TABLE wallpapers ADD COLUMN category TEXT;
+
+-- the search index gets caption and category columns, rebuilt from scratch
+DROP TRIGGER wallpapers_fts_delete;
+DROP TRIGGER wallpapers_fts_update;
+DROP TRIGGER wallpapers_fts_insert;
+DROP TABLE wallpapers_fts;
+
+CREATE VIRTUAL TABLE wallpapers_fts USING fts5(
+    wallpaper_id UNINDEXED,
+    keywords,
+    file_name,
+    caption,
+    category,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name, caption, category)
+SELECT
+    id,
+    coalesce(keywords, ''),
+    substr(path, length(rtrim(path, replace(path, '/', ''))) + 1),
+    coalesce(caption, ''),
+    coalesce(category, '')
+FROM wallpapers;
+
+CREATE TRIGGER wallpapers_fts_insert AFTER INSERT ON wallpapers BEGIN
+    INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name, caption, category)
+    VALUES (
+        new.id,
+        coalesce(new.keywords, ''),
+        substr(new.path, length(rtrim(new.path, replace(new.path, '/', ''))) + 1),
+        coalesce(new.caption, ''),
+        coalesce(new.category, '')
+    );
+END;
+
+CREATE TRIGGER wallpapers_fts_update
+AFTER UPDATE OF path, keywords, caption, category ON wallpapers BEGIN
+    DELETE FROM wallpapers_fts WHERE wallpaper_id = old.id;
+    INSERT INTO wallpapers_fts (wallpaper_id, keywords, file_name, caption, category)
+    VALUES (
+        new.id,
+        coalesce(new.keywords, ''),
+        substr(new.path, length(rtrim(new.path, replace(new.path, '/', ''))) + 1),
+        coalesce(new.caption, ''),
+        coalesce(new.category, '')
+    );
+END;
+
+CREATE TRIGGER wallpapers_fts_delete AFTER DELETE ON wallpapers BEGIN
+    DELETE FROM wallpapers_fts WHERE wallpaper_id = old.id;
+END;
@@ -0,0 +1,148 @@
+use super::{
+    cache_limit, render_unsupported, Assignment, AssignmentResult, Error, Mode, WallpaperBackend,
+};
+use crate::database::settings::get_setting;
+use crate::hyprpaper;
+use crate::utils::prescale::{self, Cache};
+use diesel::SqliteConnection;
+use std::path::PathBuf;
+
+/// Renders wallpapers at the monitor's resolution before handing them to hyprpaper, which
+/// otherwise keeps the full original in memory.
+pub struct Prescale {
+    /// Bytes
+    pub cache_limit: u64,
+}
+
+impl Prescale {
+    pub const SETTINGS: [&str; 2] = ["hyprpaper.prescale", "hyprpaper.prescale_cache_size"];
+
+    /// `None` unless `hyprpaper.prescale` is `"true"`.
+    pub fn from_settings(conn: &mut SqliteConnection) -> Result<Option<Self>, Error> {
+        let enabled =
+            get_setting(conn, Self::SETTINGS[0]).map_err(|e| Error::Config(e.to_string()))?;
+
+        if enabled.as_deref() != Some("true") {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            cache_limit: cache_limit(conn)?,
+        }))
+    }
+
+    /// Points every assignment of a scanned wallpaper at its prescaled copy, falling back
+    /// to the original when there is none.
+    fn apply(&self, assignments: &[Assignment]) -> Vec<Assignment> {
+        let monitors = match hyprpaper::monitors() {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Failed to get monitors, using the original wallpapers: {e}");
+                Vec::new()
+            }
+        };
+
+        let mut used: Vec<PathBuf> = Vec::new();
+
+        let prescaled = assignments
+            .iter()
+            .map(|a| {
+                let cached = a.signature.as_deref().and_then(|signature| {
+                    let monitor = monitors.iter().find(|m| m.name == a.screen)?;
+                    prescale::prescaled(&a.wallpaper, signature, monitor, &a.mode)
+                });
+
+                let wallpaper = match cached {
+                    Some(path) => {
+                        let wallpaper = path.to_string_lossy().to_string();
+                        used.push(path);
+                        wallpaper
+                    }
+                    None => a.wallpaper.clone(),
+                };
+
+                Assignment {
+                    screen: a.screen.clone(),
+                    wallpaper,
+                    mode: a.mode.clone(),
+                    signature: a.signature.clone(),
+                }
+            })
+            .collect();
+
+        prescale::evict(Cache::Prescaled, self.cache_limit, &used);
+
+        prescaled
+    }
+}
+
+pub struct Hyprpaper {
+    pub prescale: Option<Prescale>,
+    /// Bytes, for modes rendered by [`render_unsupported`]
+    pub cache_limit: u64,
+}
+
+impl WallpaperBackend for Hyprpaper {
+    fn cache_limit(&self) -> u64 {
+        self.cache_limit
+    }
+
+    fn supports(&self, mode: &Mode) -> bool {
+        matches!(
+            mode,
+            Mode::Default | Mode::Cover | Mode::Contain(None) | Mode::Tile | Mode::Span
+        )
+    }
+
+    fn preload(&self, wallpaper: &str) -> Result<(), Error> {
+        Ok(hyprpaper::preload(wallpaper.to_string())?)
+    }
+
+    fn unload(&self, wallpaper: Option<&str>) -> Result<(), Error> {
+        let action = match wallpaper {
+            Some(path) => hyprpaper::Unload::Path(path.to_string()),
+            None => hyprpaper::Unload::Unused,
+        };
+
+        Ok(hyprpaper::unload(action)?)
+    }
+
+    fn set(&self, screen: &str, wallpaper: &str, mode: &Mode) -> Result<(), Error> {
+        Ok(hyprpaper::set_wallpaper(
+            screen.to_string(),
+            wallpaper.to_string(),
+            mode,
+        )?)
+    }
+
+    fn set_batch(&self, assignments: &[Assignment]) -> Result<Vec<AssignmentResult>, Error> {
+        let (mut assignments, mut results) = render_unsupported(self, assignments);
+
+        // hyprpaper sets a batch as a whole, a screen that failed to render stops the rest
+        if !results.is_empty() {
+            results.extend(assignments.into_iter().map(|a| AssignmentResult {
+                screen: a.screen,
+                result: Err(Error::Aborted),
+            }));
+
+            return Ok(results);
+        }
+
+        if let Some(prescale) = &self.prescale {
+            assignments = prescale.apply(&assignments);
+        }
+
+        results.extend(hyprpaper::set_wallpapers(&assignments)?.into_iter().map(
+            |(screen, result)| AssignmentResult {
+                screen,
+                result: result.map_err(Error::from),
+            },
+        ));
+
+        Ok(results)
+    }
+
+    fn list_outputs(&self) -> Result<Vec<String>, Error> {
+        Ok(hyprpaper::active_screens()?)
+    }
+}
@@ -0,0 +1,380 @@
+mod hyprpaper;
+mod outputs;
+mod swaybg;
+mod swww;
+mod wpaperd;
+
+pub use self::hyprpaper::{Hyprpaper, Prescale};
+pub use swaybg::Swaybg;
+pub use swww::{Swww, SwwwTransition};
+pub use wpaperd::Wpaperd;
+
+use crate::database::models::Wallpaper;
+use crate::database::settings::{get_setting, BACKEND};
+use crate::hyprpaper::Monitor;
+use crate::utils::prescale::{self, Cache};
+use diesel::SqliteConnection;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Clone)]
+pub struct Assignment {
+    pub screen: String,
+    pub wallpaper: String,
+    pub mode: Mode,
+    /// Content signature of a scanned wallpaper, lets backends cache derived files
+    pub signature: Option<String>,
+}
+
+impl Assignment {
+    pub fn new(screen: String, wallpaper: String, mode: Mode) -> Self {
+        Self {
+            screen,
+            wallpaper,
+            mode,
+            signature: None,
+        }
+    }
+
+    pub fn from_wallpaper(screen: String, wallpaper: &Wallpaper, mode: Mode) -> Self {
+        Self {
+            screen,
+            wallpaper: wallpaper.path.clone(),
+            mode,
+            signature: Some(wallpaper.signature.clone()),
+        }
+    }
+}
+
+pub struct AssignmentResult {
+    pub screen: String,
+    pub result: Result<(), Error>,
+}
+
+pub trait WallpaperBackend {
+    /// Size in bytes the files rendered by [`render_unsupported`] may take up.
+    fn cache_limit(&self) -> u64;
+
+    /// Monitors the backend draws on, [`render_unsupported`] renders at their size.
+    fn monitors(&self) -> Result<Vec<Monitor>, Error> {
+        outputs::monitors()
+    }
+
+    /// Whether `set` can display `mode`, other modes are rendered before they reach the backend.
+    fn supports(&self, mode: &Mode) -> bool;
+
+    /// Loads `wallpaper` into memory ahead of `set`, no-op for backends without a cache.
+    fn preload(&self, wallpaper: &str) -> Result<(), Error>;
+
+    /// Frees `wallpaper`, or every wallpaper that isn't displayed when `None`.
+    fn unload(&self, wallpaper: Option<&str>) -> Result<(), Error>;
+
+    /// Sets `wallpaper` on `screen`, where `screen` may be `"all"`.
+    fn set(&self, screen: &str, wallpaper: &str, mode: &Mode) -> Result<(), Error>;
+
+    /// Applies all assignments in one go. `Err` means nothing could be attempted,
+    /// otherwise every assignment gets its own result.
+    fn set_batch(&self, assignments: &[Assignment]) -> Result<Vec<AssignmentResult>, Error> {
+        let (assignments, mut results) = render_unsupported(self, assignments);
+        let mut preloaded: HashSet<&str> = HashSet::new();
+
+        results.extend(assignments.iter().map(|a| {
+            let mut result = Ok(());
+
+            if preloaded.insert(&a.wallpaper) {
+                result = self.preload(&a.wallpaper);
+            }
+
+            AssignmentResult {
+                screen: a.screen.clone(),
+                result: result.and_then(|_| self.set(&a.screen, &a.wallpaper, &a.mode)),
+            }
+        }));
+
+        Ok(results)
+    }
+
+    fn list_outputs(&self) -> Result<Vec<String>, Error>;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Hyprpaper(crate::hyprpaper::Error),
+    Os(std::io::Error),
+    CommandFailed(String),
+    /// The backend program isn't installed
+    MissingCommand(String),
+    Config(String),
+    UnknownBackend(String),
+    UnknownMode(String),
+    /// The image pipeline failed to produce a mode the backend can't display
+    Render(String),
+    /// Not applied because another screen of the batch failed
+    Aborted,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Hyprpaper(e) => write!(f, "Hyprpaper error: {e}"),
+            Self::Os(e) => write!(f, "Os error: {e}"),
+            Self::CommandFailed(e) => write!(f, "Command failed: {e}"),
+            Self::MissingCommand(program) => write!(f, "Failed to find '{program}' command"),
+            Self::Config(e) => write!(f, "Config error: {e}"),
+            Self::UnknownBackend(name) => write!(f, "Unknown backend '{name}'"),
+            Self::UnknownMode(name) => write!(f, "Unknown mode '{name}'"),
+            Self::Render(e) => write!(f, "Failed to render wallpaper: {e}"),
+            Self::Aborted => write!(f, "Not applied, another screen failed"),
+        }
+    }
+}
+
+impl From<crate::hyprpaper::Error> for Error {
+    fn from(e: crate::hyprpaper::Error) -> Self {
+        Self::Hyprpaper(e)
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum BackendKind {
+    Hyprpaper,
+    Swww,
+    Swaybg,
+    Wpaperd,
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Hyprpaper => write!(f, "hyprpaper"),
+            Self::Swww => write!(f, "swww"),
+            Self::Swaybg => write!(f, "swaybg"),
+            Self::Wpaperd => write!(f, "wpaperd"),
+        }
+    }
+}
+
+impl BackendKind {
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        match string.to_lowercase().as_str() {
+            "hyprpaper" => Ok(Self::Hyprpaper),
+            "swww" => Ok(Self::Swww),
+            "swaybg" => Ok(Self::Swaybg),
+            "wpaperd" => Ok(Self::Wpaperd),
+            _ => Err(Error::UnknownBackend(string.to_string())),
+        }
+    }
+}
+
+/// Returns the backend selected in the `settings` table, defaulting to hyprpaper.
+pub fn get_backend(conn: &mut SqliteConnection) -> Result<Box<dyn WallpaperBackend>, Error> {
+    let kind = match get_setting(conn, BACKEND) {
+        Ok(Some(v)) => BackendKind::from_string(&v)?,
+        Ok(None) => BackendKind::Hyprpaper,
+        Err(e) => return Err(Error::Config(e.to_string())),
+    };
+
+    Ok(match kind {
+        BackendKind::Hyprpaper => Box::new(Hyprpaper {
+            prescale: Prescale::from_settings(conn)?,
+            cache_limit: cache_limit(conn)?,
+        }),
+        BackendKind::Swww => Box::new(Swww {
+            transition: SwwwTransition::from_settings(conn)?,
+            cache_limit: cache_limit(conn)?,
+        }),
+        BackendKind::Swaybg => Box::new(Swaybg {
+            cache_limit: cache_limit(conn)?,
+        }),
+        BackendKind::Wpaperd => Box::new(Wpaperd {
+            cache_limit: cache_limit(conn)?,
+        }),
+    })
+}
+
+/// `hyprpaper.prescale_cache_size` in bytes, the setting is in MiB. Every cache of rendered
+/// wallpapers gets this much.
+pub fn cache_limit(conn: &mut SqliteConnection) -> Result<u64, Error> {
+    let key = Prescale::SETTINGS[1];
+
+    let cache_size = match get_setting(conn, key) {
+        Ok(Some(v)) => v
+            .parse::<u64>()
+            .map_err(|e| Error::Config(format!("{key}: {e}")))?,
+        Ok(None) => prescale::DEFAULT_CACHE_SIZE_MB,
+        Err(e) => return Err(Error::Config(e.to_string())),
+    };
+
+    Ok(cache_size * 1024 * 1024)
+}
+
+/// Resolves `"all"` to the backend's outputs.
+pub fn expand_screen(backend: &dyn WallpaperBackend, screen: &str) -> Result<Vec<String>, Error> {
+    if screen == "all" {
+        backend.list_outputs()
+    } else {
+        Ok(vec![screen.to_string()])
+    }
+}
+
+/// What fills the borders around a contained image.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Background {
+    Color([u8; 3]),
+    /// The image itself, covering the screen and blurred
+    Blur,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Mode {
+    /// What the backend does without options, covers the screen on all of them
+    Default,
+    /// Scaled to cover the screen, the overflow is cropped
+    Cover,
+    /// Scaled to fit the screen, `None` leaves the borders to the backend
+    Contain(Option<Background>),
+    /// Scaled to the screen size, ignoring the aspect ratio
+    Stretch,
+    /// Original size in the middle of the screen
+    Center,
+    Tile,
+    /// One image across several screens, each gets its slice as a `Default` wallpaper
+    Span,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Cover => write!(f, "cover"),
+            Self::Contain(None) => write!(f, "contain"),
+            Self::Contain(Some(Background::Color([r, g, b]))) => {
+                write!(f, "contain:#{r:02x}{g:02x}{b:02x}")
+            }
+            Self::Contain(Some(Background::Blur)) => write!(f, "contain:blur"),
+            Self::Stretch => write!(f, "stretch"),
+            Self::Center => write!(f, "center"),
+            Self::Tile => write!(f, "tile"),
+            Self::Span => write!(f, "span"),
+        }
+    }
+}
+
+impl Mode {
+    /// Parses the `Display` form, a contain background is `contain:#rrggbb` or `contain:blur`.
+    pub fn from_string(string: &str) -> Result<Self, Error> {
+        let lower = string.to_lowercase();
+
+        let mode = match lower.split_once(':') {
+            Some(("contain", "blur")) => Some(Mode::Contain(Some(Background::Blur))),
+            Some(("contain", color)) => parse_color(color).map(|c| Mode::Contain(Some(c))),
+            Some(_) => None,
+            None => match lower.as_str() {
+                "default" => Some(Mode::Default),
+                "cover" => Some(Mode::Cover),
+                "contain" => Some(Mode::Contain(None)),
+                "stretch" => Some(Mode::Stretch),
+                "center" => Some(Mode::Center),
+                "tile" => Some(Mode::Tile),
+                "span" => Some(Mode::Span),
+                _ => None,
+            },
+        };
+
+        mode.ok_or_else(|| Error::UnknownMode(string.to_string()))
+    }
+}
+
+/// `#rrggbb`, the `#` is optional.
+fn parse_color(string: &str) -> Option<Background> {
+    let hex = string.strip_prefix('#').unwrap_or(string);
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some(Background::Color([channel(0)?, channel(2)?, channel(4)?]))
+}
+
+/// Swaps every assignment the backend can't display for a copy rendered at the monitor's
+/// resolution, which any backend shows as is. Failed renders come back as results.
+pub(crate) fn render_unsupported<B: WallpaperBackend + ?Sized>(
+    backend: &B,
+    assignments: &[Assignment],
+) -> (Vec<Assignment>, Vec<AssignmentResult>) {
+    if assignments.iter().all(|a| backend.supports(&a.mode)) {
+        return (assignments.to_vec(), Vec::new());
+    }
+
+    let monitors = backend.monitors();
+    let mut rendered: Vec<Assignment> = Vec::with_capacity(assignments.len());
+    let mut failed: Vec<AssignmentResult> = Vec::new();
+    let mut used: Vec<PathBuf> = Vec::new();
+
+    for a in assignments {
+        if backend.supports(&a.mode) {
+            rendered.push(a.clone());
+            continue;
+        }
+
+        let result = match &monitors {
+            Ok(monitors) => match monitors.iter().find(|m| m.name == a.screen) {
+                Some(monitor) => {
+                    prescale::rendered(&a.wallpaper, a.signature.as_deref(), monitor, &a.mode)
+                        .map_err(|e| Error::Render(format!("'{}': {e}", a.wallpaper)))
+                }
+                None => Err(Error::Render(format!("no monitor '{}'", a.screen))),
+            },
+            Err(e) => Err(Error::Render(format!("failed to get monitors: {e}"))),
+        };
+
+        match result {
+            Ok(path) => {
+                rendered.push(Assignment {
+                    screen: a.screen.clone(),
+                    wallpaper: path.to_string_lossy().to_string(),
+                    mode: Mode::Default,
+                    // Already at the monitor's size, and not the file the signature belongs to
+                    signature: None,
+                });
+                used.push(path);
+            }
+            Err(e) => failed.push(AssignmentResult {
+                screen: a.screen.clone(),
+                result: Err(e),
+            }),
+        }
+    }
+
+    prescale::evict(Cache::Rendered, backend.cache_limit(), &used);
+
+    (rendered, failed)
+}
+
+/// Runs `cmd` to completion, mapping a non-zero exit status to `Error::CommandFailed`.
+fn run_command(cmd: &mut std::process::Command) -> Result<String, Error> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+
+    match cmd.output() {
+        Ok(output) => {
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                log::error!("'{program}' failed: {stderr}");
+                Err(Error::CommandFailed(format!("{program}: {stderr}")))
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::error!("Failed to find '{program}': {e}");
+            Err(Error::MissingCommand(program))
+        }
+        Err(e) => {
+            log::error!("Failed to run '{program}': {e}");
+            Err(Error::Os(e))
+        }
+    }
+}
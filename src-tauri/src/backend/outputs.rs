@@ -0,0 +1,119 @@
+use super::{run_command, Error};
+use crate::hyprpaper::{self, Monitor};
+use serde::Deserialize;
+use std::process;
+
+const WLR_RANDR_CMD: &str = "wlr-randr";
+
+#[derive(Deserialize)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    current: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct WlrPosition {
+    x: i32,
+    y: i32,
+}
+
+/// The part of a `wlr-randr --json` entry we use.
+#[derive(Deserialize)]
+struct WlrOutput {
+    name: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+    #[serde(default)]
+    position: WlrPosition,
+    transform: Option<String>,
+    scale: Option<f64>,
+}
+
+/// Whether we run inside Hyprland, which sets the variable for the programs it starts.
+pub fn is_hyprland() -> bool {
+    std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+}
+
+/// wl_output transform of a `wlr-randr` transform name.
+fn transform(name: &str) -> u8 {
+    match name {
+        "90" => 1,
+        "180" => 2,
+        "270" => 3,
+        "flipped" => 4,
+        "flipped-90" => 5,
+        "flipped-180" => 6,
+        "flipped-270" => 7,
+        _ => 0,
+    }
+}
+
+/// Enabled outputs with a current mode.
+fn parse_wlr_randr(json: &str) -> Result<Vec<Monitor>, Error> {
+    let outputs: Vec<WlrOutput> = serde_json::from_str(json)
+        .map_err(|e| Error::CommandFailed(format!("{WLR_RANDR_CMD}: {e}")))?;
+
+    Ok(outputs
+        .into_iter()
+        .filter(|o| o.enabled)
+        .filter_map(|o| {
+            let mode = o.modes.iter().find(|m| m.current)?;
+
+            Some(Monitor {
+                x: o.position.x,
+                y: o.position.y,
+                width: mode.width,
+                height: mode.height,
+                scale: o.scale.unwrap_or(1.0),
+                transform: o.transform.as_deref().map_or(0, transform),
+                name: o.name,
+            })
+        })
+        .collect())
+}
+
+/// Monitors of the running compositor, from Hyprland when it's running and from
+/// `wlr-randr` on other wlroots compositors.
+pub fn monitors() -> Result<Vec<Monitor>, Error> {
+    if is_hyprland() {
+        return Ok(hyprpaper::monitors()?);
+    }
+
+    parse_wlr_randr(&run_command(
+        process::Command::new(WLR_RANDR_CMD).arg("--json"),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wlr_randr_current_modes_of_enabled_outputs() {
+        let json = r#"[
+            {"name": "DP-1", "enabled": true, "scale": 1.5, "transform": "90",
+             "position": {"x": 1920, "y": 0},
+             "modes": [{"width": 3840, "height": 2160, "refresh": 60.0, "current": true},
+                       {"width": 1920, "height": 1080, "refresh": 60.0, "current": false}]},
+            {"name": "HDMI-A-1", "enabled": false, "modes": []},
+            {"name": "eDP-1", "enabled": true, "modes": [{"width": 1920, "height": 1080}]}
+        ]"#;
+
+        let monitors = parse_wlr_randr(json).unwrap();
+
+        assert_eq!(monitors.len(), 1);
+        assert_eq!(monitors[0].name, "DP-1");
+        assert_eq!((monitors[0].x, monitors[0].y), (1920, 0));
+        assert_eq!(monitors[0].scale, 1.5);
+        assert_eq!(monitors[0].pixel_size(), (2160, 3840));
+    }
+
+    #[test]
+    fn wlr_randr_garbage() {
+        assert!(parse_wlr_randr("DP-1 connected").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use super::{outputs, Background, Error, Mode, WallpaperBackend};
+use std::os::unix::process::CommandExt;
+use std::process::{self, Stdio};
+
+const SWAYBG_CMD: &str = "swaybg";
+
+/// swaybg has no IPC, every wallpaper is a long-running `swaybg` process.
+pub struct Swaybg {
+    /// Bytes, for modes rendered by [`super::render_unsupported`]
+    pub cache_limit: u64,
+}
+
+/// A running swaybg process.
+struct Instance {
+    pid: i32,
+    /// Value of `-o`, drawing on every output when `None` or `"*"`
+    output: Option<String>,
+    /// Everything after the program name
+    args: Vec<String>,
+}
+
+impl Instance {
+    fn is_catch_all(&self) -> bool {
+        self.output.as_deref().is_none_or(|x| x == "*")
+    }
+
+    /// Arguments drawing the same wallpaper on `screen` alone.
+    fn args_for(&self, screen: &str) -> Vec<String> {
+        let mut args = vec![String::from("-o"), screen.to_string()];
+        let mut rest = self.args.iter();
+
+        while let Some(arg) = rest.next() {
+            if arg == "-o" || arg == "--output" {
+                rest.next();
+            } else {
+                args.push(arg.clone());
+            }
+        }
+
+        args
+    }
+}
+
+fn running_instances() -> Vec<Instance> {
+    let mut instances = Vec::new();
+
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return instances;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let Ok(pid) = entry.file_name().to_string_lossy().parse::<i32>() else {
+            continue;
+        };
+
+        let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+
+        // Every argument ends with a NUL
+        let mut args: Vec<String> = cmdline
+            .strip_suffix(&[0])
+            .unwrap_or(&cmdline)
+            .split(|b| *b == 0)
+            .map(|x| String::from_utf8_lossy(x).to_string())
+            .collect();
+
+        let is_swaybg = args
+            .first()
+            .is_some_and(|x| x.rsplit('/').next() == Some(SWAYBG_CMD));
+
+        if !is_swaybg {
+            continue;
+        }
+
+        args.remove(0);
+
+        let output = args
+            .iter()
+            .position(|x| x == "-o" || x == "--output")
+            .and_then(|i| args.get(i + 1))
+            .cloned();
+
+        instances.push(Instance { pid, output, args });
+    }
+
+    instances
+}
+
+/// Starts swaybg with `args`, returning its pid.
+fn spawn(args: &[String]) -> Result<i32, Error> {
+    // Own process group so swaybg outlives us when running from the CLI
+    match process::Command::new(SWAYBG_CMD)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+    {
+        Ok(child) => Ok(child.id() as i32),
+        Err(e) => {
+            log::error!("Failed to spawn swaybg: {e}");
+
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return Err(Error::MissingCommand(String::from("swaybg")));
+            }
+
+            Err(Error::Os(e))
+        }
+    }
+}
+
+fn stop(pid: i32) {
+    // SAFETY: kill only sends a signal, the pid was just read from /proc or spawned by us
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+        let e = std::io::Error::last_os_error();
+        log::warn!("Failed to stop swaybg ({pid}): {e}");
+    }
+}
+
+impl WallpaperBackend for Swaybg {
+    fn cache_limit(&self) -> u64 {
+        self.cache_limit
+    }
+
+    fn supports(&self, mode: &Mode) -> bool {
+        *mode != Mode::Contain(Some(Background::Blur))
+    }
+
+    fn preload(&self, _wallpaper: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn unload(&self, _wallpaper: Option<&str>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set(&self, screen: &str, wallpaper: &str, mode: &Mode) -> Result<(), Error> {
+        let swaybg_mode = match mode {
+            Mode::Default | Mode::Cover | Mode::Span => "fill",
+            Mode::Contain(_) => "fit",
+            Mode::Stretch => "stretch",
+            Mode::Center => "center",
+            Mode::Tile => "tile",
+        };
+        let output = if screen == "all" { "*" } else { screen };
+        let mut args: Vec<String> = ["-o", output, "-m", swaybg_mode, "-i", wallpaper]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        // `-c` fills whatever the image doesn't cover
+        if let Mode::Contain(Some(Background::Color([r, g, b]))) = mode {
+            args.extend([String::from("-c"), format!("#{r:02x}{g:02x}{b:02x}")]);
+        }
+
+        let instances = running_instances();
+        let (catch_all, own): (Vec<&Instance>, Vec<&Instance>) =
+            instances.iter().partition(|i| i.is_catch_all());
+        // Other outputs keep their instances, `"all"` replaces every one
+        let previous: Vec<&Instance> = if screen == "all" {
+            instances.iter().collect()
+        } else {
+            own.iter()
+                .copied()
+                .filter(|i| i.output.as_deref() == Some(screen))
+                .collect()
+        };
+
+        let pid = spawn(&args)?;
+
+        // An instance drawing on every output is split into one per output, so that
+        // replacing it doesn't blank the others
+        if let Some(instance) = catch_all.first().filter(|_| screen != "all") {
+            match self.list_outputs() {
+                Ok(outputs) => {
+                    let uncovered = outputs.iter().filter(|x| {
+                        *x != screen && !own.iter().any(|i| i.output.as_ref() == Some(*x))
+                    });
+                    let mut started = vec![pid];
+
+                    for other in uncovered {
+                        match spawn(&instance.args_for(other)) {
+                            Ok(pid) => started.push(pid),
+                            Err(e) => {
+                                // Back to the instances running before, rather than two
+                                // processes drawing on the same outputs
+                                for pid in started {
+                                    stop(pid);
+                                }
+
+                                return Err(e);
+                            }
+                        }
+                    }
+
+                    for i in &catch_all {
+                        stop(i.pid);
+                    }
+                }
+                Err(e) => log::warn!("Failed to list outputs, keeping swaybg on all: {e}"),
+            }
+        }
+
+        // Killing the old instance only after the new one started avoids a blank frame
+        for i in previous {
+            stop(i.pid);
+        }
+
+        Ok(())
+    }
+
+    fn list_outputs(&self) -> Result<Vec<String>, Error> {
+        Ok(outputs::monitors()?.into_iter().map(|m| m.name).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_all_copy_for_one_output() {
+        let instance = Instance {
+            pid: 1,
+            output: Some(String::from("*")),
+            args: ["-m", "fill", "-o", "*", "-i", "/a.png"]
+                .map(String::from)
+                .to_vec(),
+        };
+
+        assert!(instance.is_catch_all());
+        assert_eq!(
+            instance.args_for("DP-2"),
+            ["-o", "DP-2", "-m", "fill", "-i", "/a.png"]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use super::{
+    outputs, render_unsupported, run_command, Assignment, AssignmentResult, Background, Error,
+    Mode, WallpaperBackend,
+};
+use crate::database::settings::get_setting;
+use crate::hyprpaper::Monitor;
+use diesel::SqliteConnection;
+use std::process;
+
+const SWWW_CMD: &str = "swww";
+
+/// `swww img` transition options, each one maps to the `--transition-<name>` flag.
+#[derive(Default)]
+pub struct SwwwTransition {
+    pub kind: Option<String>,
+    pub step: Option<String>,
+    pub fps: Option<String>,
+    pub duration: Option<String>,
+    pub pos: Option<String>,
+}
+
+impl SwwwTransition {
+    pub const SETTINGS: [&str; 5] = [
+        "swww.transition_type",
+        "swww.transition_step",
+        "swww.transition_fps",
+        "swww.transition_duration",
+        "swww.transition_pos",
+    ];
+
+    pub fn from_settings(conn: &mut SqliteConnection) -> Result<Self, Error> {
+        let mut values = Vec::with_capacity(Self::SETTINGS.len());
+
+        for key in Self::SETTINGS {
+            match get_setting(conn, key) {
+                Ok(v) => values.push(v.filter(|x| !x.is_empty())),
+                Err(e) => return Err(Error::Config(e.to_string())),
+            }
+        }
+
+        let mut values = values.into_iter();
+
+        Ok(Self {
+            kind: values.next().flatten(),
+            step: values.next().flatten(),
+            fps: values.next().flatten(),
+            duration: values.next().flatten(),
+            pos: values.next().flatten(),
+        })
+    }
+
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        for (flag, value) in [
+            ("--transition-type", &self.kind),
+            ("--transition-step", &self.step),
+            ("--transition-fps", &self.fps),
+            ("--transition-duration", &self.duration),
+            ("--transition-pos", &self.pos),
+        ] {
+            if let Some(v) = value {
+                args.push(flag.to_string());
+                args.push(v.clone());
+            }
+        }
+
+        args
+    }
+}
+
+/// Outputs of a `swww query` reply, lines look like
+/// `DP-1: 2560x1440, scale: 1, currently displaying: ...` and older releases prefix them
+/// with `: `. The size is in pixels, the position isn't reported.
+fn parse_query(reply: &str) -> Vec<Monitor> {
+    reply
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.trim_start_matches(": ").split_once(':')?;
+            let mut fields = rest.split(',').map(str::trim);
+            let (width, height) = fields.next()?.split_once('x')?;
+            let scale = fields
+                .find_map(|f| f.strip_prefix("scale:"))
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .unwrap_or(1.0);
+
+            Some(Monitor {
+                name: name.trim().to_string(),
+                x: 0,
+                y: 0,
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                scale,
+                transform: 0,
+            })
+        })
+        .collect()
+}
+
+pub struct Swww {
+    pub transition: SwwwTransition,
+    /// Bytes, for modes rendered by [`render_unsupported`]
+    pub cache_limit: u64,
+}
+
+impl WallpaperBackend for Swww {
+    fn cache_limit(&self) -> u64 {
+        self.cache_limit
+    }
+
+    /// The compositor knows the positions, `swww query` is enough for sizes elsewhere.
+    fn monitors(&self) -> Result<Vec<Monitor>, Error> {
+        if outputs::is_hyprland() {
+            return outputs::monitors();
+        }
+
+        Ok(parse_query(&run_command(
+            process::Command::new(SWWW_CMD).arg("query"),
+        )?))
+    }
+
+    fn supports(&self, mode: &Mode) -> bool {
+        !matches!(mode, Mode::Stretch | Mode::Contain(Some(Background::Blur)))
+    }
+
+    fn preload(&self, _wallpaper: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn unload(&self, _wallpaper: Option<&str>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set(&self, screen: &str, wallpaper: &str, mode: &Mode) -> Result<(), Error> {
+        let resize = match mode {
+            Mode::Default | Mode::Cover | Mode::Span => "crop",
+            Mode::Contain(_) => "fit",
+            Mode::Center | Mode::Tile => "no",
+            // Not in `supports`, `render_unsupported` prescales it
+            Mode::Stretch => {
+                return Err(Error::Render(String::from("swww can't stretch an image")));
+            }
+        };
+
+        let mut cmd = process::Command::new(SWWW_CMD);
+        cmd.arg("img");
+
+        // `--outputs` takes a comma separated list, `set_batch` relies on that
+        if screen != "all" {
+            cmd.args(["--outputs", screen]);
+        }
+
+        if let Mode::Contain(Some(Background::Color([r, g, b]))) = mode {
+            cmd.args(["--fill-color", &format!("{r:02x}{g:02x}{b:02x}")]);
+        }
+
+        cmd.args(["--resize", resize])
+            .args(self.transition.args())
+            .arg(wallpaper);
+
+        run_command(&mut cmd).map(|_| ())
+    }
+
+    /// One `swww img` per distinct wallpaper and mode, so screens sharing an image
+    /// transition together.
+    fn set_batch(&self, assignments: &[Assignment]) -> Result<Vec<AssignmentResult>, Error> {
+        let (assignments, mut results) = render_unsupported(self, assignments);
+        let mut groups: Vec<(&str, &Mode, Vec<&str>)> = Vec::new();
+
+        for a in &assignments {
+            match groups
+                .iter_mut()
+                .find(|(wallpaper, mode, _)| *wallpaper == a.wallpaper && **mode == a.mode)
+            {
+                Some((_, _, screens)) => screens.push(&a.screen),
+                None => groups.push((&a.wallpaper, &a.mode, vec![&a.screen])),
+            }
+        }
+
+        for (wallpaper, mode, screens) in groups {
+            let result = self.set(&screens.join(","), wallpaper, mode);
+
+            for screen in screens {
+                results.push(AssignmentResult {
+                    screen: screen.to_string(),
+                    result: match &result {
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(Error::CommandFailed(e.to_string())),
+                    },
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn list_outputs(&self) -> Result<Vec<String>, Error> {
+        let output = run_command(process::Command::new(SWWW_CMD).arg("query"))?;
+
+        Ok(parse_query(&output).into_iter().map(|m| m.name).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_sizes() {
+        let reply = "DP-1: 2560x1440, scale: 1.5, currently displaying: image: /a.png\n\
+                     : HDMI-A-1: 1920x1080, scale: 1, currently displaying: color: 000000\n\
+                     eDP-1: unknown\n";
+
+        let monitors = parse_query(reply);
+
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[0].name, "DP-1");
+        assert_eq!(monitors[0].pixel_size(), (2560, 1440));
+        assert_eq!(monitors[0].scale, 1.5);
+        assert_eq!(monitors[1].name, "HDMI-A-1");
+        assert_eq!(monitors[1].scale, 1.0);
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    outputs, render_unsupported, Assignment, AssignmentResult, Error, Mode, WallpaperBackend,
+};
+use std::path::PathBuf;
+use toml_edit::DocumentMut;
+
+/// wpaperd watches its config file and reloads it, so setting a wallpaper means editing it.
+pub struct Wpaperd {
+    /// Bytes, for modes rendered by [`render_unsupported`]
+    pub cache_limit: u64,
+}
+
+fn config_path() -> Result<PathBuf, Error> {
+    match directories::BaseDirs::new() {
+        Some(dirs) => Ok(dirs.config_dir().join("wpaperd").join("config.toml")),
+        None => Err(Error::Config(String::from("Failed to get base dir"))),
+    }
+}
+
+/// Sets `path` and `mode` in the sections of the screens, comments and order are kept.
+fn edit_config(config: &mut DocumentMut, assignments: &[(&str, &str, &Mode)]) -> Result<(), Error> {
+    for (screen, wallpaper, mode) in assignments {
+        let wpaperd_mode = match mode {
+            Mode::Default | Mode::Cover | Mode::Span | Mode::Center => "center",
+            Mode::Contain(_) => "fit",
+            Mode::Stretch => "stretch",
+            Mode::Tile => "tile",
+        };
+        let section = if *screen == "all" { "any" } else { screen };
+
+        if *screen == "all" {
+            // Per-output entries would take precedence over `[any]`
+            for (_, item) in config.iter_mut() {
+                if let Some(table) = item.as_table_like_mut() {
+                    table.remove("path");
+                }
+            }
+        }
+
+        let entry = config.entry(section).or_insert(toml_edit::table());
+
+        let Some(table) = entry.as_table_like_mut() else {
+            return Err(Error::Config(format!("'{section}' is not a table")));
+        };
+
+        table.insert("path", toml_edit::value(*wallpaper));
+        table.insert("mode", toml_edit::value(wpaperd_mode));
+    }
+
+    Ok(())
+}
+
+/// Writes all assignments in a single config update so wpaperd only reloads once.
+fn write_config(assignments: &[(&str, &str, &Mode)]) -> Result<(), Error> {
+    let path = config_path()?;
+
+    let mut config = match std::fs::read_to_string(&path) {
+        Ok(text) => match text.parse::<DocumentMut>() {
+            Ok(v) => v,
+            Err(e) => return Err(Error::Config(format!("{}: {e}", path.display()))),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+        Err(e) => return Err(Error::Os(e)),
+    };
+
+    edit_config(&mut config, assignments)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(Error::Os)?;
+    }
+
+    // wpaperd reloads on change, it must never see a half-written file
+    let tmp = path.with_extension("toml.tmp");
+
+    std::fs::write(&tmp, config.to_string()).map_err(Error::Os)?;
+    std::fs::rename(&tmp, &path).map_err(Error::Os)
+}
+
+impl WallpaperBackend for Wpaperd {
+    fn cache_limit(&self) -> u64 {
+        self.cache_limit
+    }
+
+    fn supports(&self, mode: &Mode) -> bool {
+        !matches!(mode, Mode::Center | Mode::Contain(Some(_)))
+    }
+
+    fn preload(&self, _wallpaper: &str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn unload(&self, _wallpaper: Option<&str>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn set(&self, screen: &str, wallpaper: &str, mode: &Mode) -> Result<(), Error> {
+        write_config(&[(screen, wallpaper, mode)])
+    }
+
+    fn set_batch(&self, assignments: &[Assignment]) -> Result<Vec<AssignmentResult>, Error> {
+        let (assignments, mut results) = render_unsupported(self, assignments);
+
+        let list: Vec<(&str, &str, &Mode)> = assignments
+            .iter()
+            .map(|a| (a.screen.as_str(), a.wallpaper.as_str(), &a.mode))
+            .collect();
+
+        write_config(&list)?;
+
+        results.extend(assignments.iter().map(|a| AssignmentResult {
+            screen: a.screen.clone(),
+            result: Ok(()),
+        }));
+
+        Ok(results)
+    }
+
+    fn list_outputs(&self) -> Result<Vec<String>, Error> {
+        Ok(outputs::monitors()?.into_iter().map(|m| m.name).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# set by hand\n\
+                          [default]\n\
+                          duration = \"30m\" # every half hour\n\
+                          \n\
+                          [DP-1]\n\
+                          mode = \"tile\"\n\
+                          path = \"/old.png\"\n\
+                          sorting = \"random\"\n";
+
+    #[test]
+    fn keeps_comments_and_order() {
+        let mut config: DocumentMut = CONFIG.parse().unwrap();
+
+        edit_config(&mut config, &[("DP-1", "/new.png", &Mode::Stretch)]).unwrap();
+
+        assert_eq!(
+            config.to_string(),
+            CONFIG
+                .replace("\"tile\"", "\"stretch\"")
+                .replace("/old.png", "/new.png")
+        );
+    }
+
+    #[test]
+    fn all_replaces_output_paths() {
+        let mut config: DocumentMut = CONFIG.parse().unwrap();
+
+        edit_config(&mut config, &[("all", "/new.png", &Mode::Default)]).unwrap();
+
+        assert!(config["DP-1"].get("path").is_none());
+        assert_eq!(config["any"]["path"].as_str(), Some("/new.png"));
+        assert!(config.to_string().starts_with("# set by hand\n[default]"));
+    }
+}
@@ -0,0 +1,383 @@
+mod run;
+
+pub use run::{error_json, print_error, render, run, run_config};
+
+use crate::ipc::{WallpaperFilter, WallpaperSearch};
+use crate::utils::phash;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::Path;
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+pub struct Cli {
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    #[arg(short, long)]
+    pub restore: bool,
+
+    /// Run the wallpaper rotations and the source watcher in the foreground, without the GUI
+    #[arg(short, long)]
+    pub daemon: bool,
+
+    /// Print results and errors as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Set a wallpaper on a screen
+    Set {
+        /// Screen name, or `all`
+        screen: String,
+
+        /// Wallpaper id or path
+        wallpaper: String,
+
+        /// default, cover, contain, contain:#rrggbb, contain:blur, stretch, center, tile or span,
+        /// the README describes each
+        #[arg(short, long, default_value = "default")]
+        mode: String,
+
+        /// Don't store the wallpaper as active
+        #[arg(short, long)]
+        temporary: bool,
+    },
+
+    /// Set a random wallpaper from the active sources
+    Random {
+        /// Only pick from favorites
+        #[arg(short, long)]
+        favorites: bool,
+
+        /// Pick from this collection (id or name) instead of the active sources
+        #[arg(short, long)]
+        collection: Option<String>,
+
+        #[arg(short, long, default_value = "all")]
+        screen: String,
+
+        /// See `set --mode`
+        #[arg(short, long, default_value = "default")]
+        mode: String,
+    },
+
+    /// List wallpapers, sources, screens or active wallpapers
+    List {
+        #[arg(value_enum)]
+        target: ListTarget,
+
+        /// Only used by `list wallpapers`
+        #[command(flatten)]
+        filter: WallpaperFilter,
+    },
+
+    /// Search the wallpapers of the active sources one page at a time
+    Search {
+        #[command(flatten)]
+        search: WallpaperSearch,
+    },
+
+    /// Manage wallpaper sources
+    Source {
+        #[command(subcommand)]
+        action: SourceAction,
+    },
+
+    /// Scan one source, or every source when omitted
+    Scan {
+        /// Source id
+        source: Option<String>,
+    },
+
+    /// Mark a wallpaper as favorite
+    Favorite {
+        /// Wallpaper id
+        id: String,
+
+        /// Remove the favorite mark instead
+        #[arg(short, long)]
+        unset: bool,
+    },
+
+    /// Restore the active wallpapers
+    Restore,
+
+    /// Read or change settings
+    Setting {
+        #[command(subcommand)]
+        action: SettingAction,
+    },
+
+    /// Manage per-screen wallpaper rotations
+    Rotation {
+        #[command(subcommand)]
+        action: RotationAction,
+    },
+
+    /// Browse previously set wallpapers
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+
+    /// List wallpapers that look alike and files with the same content, best resolution first
+    Duplicates {
+        /// Differing hash bits up to which two wallpapers count as alike, 0 to 64
+        #[arg(long, default_value_t = phash::DEFAULT_MAX_DISTANCE)]
+        max_distance: u32,
+    },
+
+    /// Manage collections, named and ordered lists of wallpapers
+    Collection {
+        #[command(subcommand)]
+        action: CollectionAction,
+    },
+
+    /// Tag wallpapers, `list wallpapers --tags` filters by tag
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// Inspect the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+impl Command {
+    /// Makes relative paths absolute for a command run from another directory, values that
+    /// don't exist under `cwd` are left alone since they may be ids.
+    pub fn resolve_paths(&mut self, cwd: &Path) {
+        let values: Vec<&mut String> = match self {
+            Self::Set { wallpaper, .. } => vec![wallpaper],
+            Self::Source {
+                action: SourceAction::Add { path },
+            } => vec![path],
+            Self::Collection {
+                action:
+                    CollectionAction::Add { wallpapers, .. }
+                    | CollectionAction::Remove { wallpapers, .. },
+            } => wallpapers.iter_mut().collect(),
+            Self::Collection {
+                action: CollectionAction::Move { wallpaper, .. },
+            } => vec![wallpaper],
+            Self::Tag {
+                action: TagAction::Add { wallpapers, .. } | TagAction::Remove { wallpapers, .. },
+            } => wallpapers.iter_mut().collect(),
+            Self::Tag {
+                action: TagAction::Show { wallpaper },
+            } => vec![wallpaper],
+            _ => return,
+        };
+
+        for value in values {
+            let path = cwd.join(&*value);
+
+            if Path::new(value.as_str()).is_relative() && path.exists() {
+                *value = path.to_string_lossy().to_string();
+            }
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ListTarget {
+    Wallpapers,
+    Sources,
+    Screens,
+    Active,
+}
+
+#[derive(Subcommand)]
+pub enum SourceAction {
+    /// Add a directory as a source
+    Add {
+        path: String,
+    },
+
+    /// Remove a source and its wallpapers
+    Remove {
+        id: String,
+    },
+
+    Enable {
+        id: String,
+    },
+
+    Disable {
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SettingAction {
+    List,
+
+    Set { key: String, value: String },
+}
+
+/// Collections are given by id or name, wallpapers by id or path.
+#[derive(Subcommand)]
+pub enum CollectionAction {
+    List,
+
+    /// List the wallpapers of a collection in order
+    Show {
+        collection: String,
+    },
+
+    Create {
+        name: String,
+    },
+
+    Rename {
+        collection: String,
+        name: String,
+    },
+
+    /// Delete a collection, its wallpapers are kept
+    Delete {
+        collection: String,
+    },
+
+    /// Append wallpapers to a collection
+    Add {
+        collection: String,
+
+        #[arg(required = true)]
+        wallpapers: Vec<String>,
+    },
+
+    /// Take wallpapers out of a collection
+    Remove {
+        collection: String,
+
+        #[arg(required = true)]
+        wallpapers: Vec<String>,
+    },
+
+    /// Move a wallpaper of a collection to another position, 0 is the first
+    Move {
+        collection: String,
+        wallpaper: String,
+        position: usize,
+    },
+}
+
+/// Wallpapers are given by id or path.
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// List every tag with its number of wallpapers
+    List,
+
+    /// List the tags of a wallpaper and where they came from
+    Show { wallpaper: String },
+
+    /// Tag wallpapers
+    Add {
+        /// Repeatable or comma separated
+        #[arg(short, long = "tag", required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        #[arg(required = true)]
+        wallpapers: Vec<String>,
+    },
+
+    /// Take tags off wallpapers, whatever their origin
+    Remove {
+        /// Repeatable or comma separated
+        #[arg(short, long = "tag", required = true, value_delimiter = ',')]
+        tags: Vec<String>,
+
+        #[arg(required = true)]
+        wallpapers: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the default configuration, with comments
+    PrintDefault,
+
+    /// Validate the configuration file
+    Check,
+}
+
+#[derive(Subcommand)]
+pub enum RotationAction {
+    List,
+
+    /// Create or replace the rotation of a screen
+    Set {
+        /// Screen name, or `all`
+        screen: String,
+
+        /// Seconds between wallpaper changes
+        #[arg(short, long)]
+        interval: i32,
+
+        /// Only pick from this source id
+        #[arg(short, long)]
+        source: Option<String>,
+
+        /// Only pick from this collection (id or name), in its order with `--sequential`
+        #[arg(short, long)]
+        collection: Option<String>,
+
+        /// Only pick from favorites
+        #[arg(short, long)]
+        favorites: bool,
+
+        /// Go through the wallpapers in path order instead of shuffling
+        #[arg(long)]
+        sequential: bool,
+
+        /// See `set --mode`
+        #[arg(short, long, default_value = "default")]
+        mode: String,
+
+        /// Store the rotation without running it
+        #[arg(long)]
+        disabled: bool,
+    },
+
+    /// Switch to the next wallpaper of a rotation now
+    Next {
+        screen: String,
+    },
+
+    Remove {
+        screen: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HistoryAction {
+    /// Show recent wallpaper changes, newest first
+    List {
+        #[arg(short, long)]
+        screen: Option<String>,
+
+        #[arg(short, long, default_value_t = 20)]
+        limit: i64,
+    },
+
+    /// Bring back the previous wallpaper
+    Back {
+        #[arg(default_value = "all")]
+        screen: String,
+    },
+
+    /// Undo a `back`
+    Forward {
+        #[arg(default_value = "all")]
+        screen: String,
+    },
+}
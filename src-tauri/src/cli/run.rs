@@ -0,0 +1,545 @@
+use super::{
+    CollectionAction, Command, ConfigAction, HistoryAction, ListTarget, RotationAction,
+    SettingAction, SourceAction, TagAction,
+};
+use crate::backend::{self, Assignment};
+use crate::commands;
+use crate::config;
+use crate::database::connection::DbPool;
+use crate::database::history::{Direction, Origin};
+use crate::database::models::*;
+use crate::error::Error;
+use crate::ipc::Response;
+use crate::utils::scan::{ScanContext, ScanControl};
+use crate::utils::{hooks, restore};
+use diesel::SqliteConnection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::async_runtime;
+
+/// What a subcommand produced, printed either as text or as a JSON [`Response`].
+enum Output {
+    Wallpapers(Vec<Wallpaper>),
+    Page(WallpaperPage),
+    Wallpaper(Box<Wallpaper>),
+    Sources(Vec<WallpaperSource>),
+    Source(WallpaperSource),
+    Screens(Vec<String>),
+    Active(Vec<Active>),
+    Settings(BTreeMap<String, String>),
+    Rotations(Vec<Rotation>),
+    History(Vec<History>),
+    Duplicates(Vec<DuplicateCluster>),
+    Collections(Vec<Collection>),
+    Tags(Vec<TagCount>),
+    WallpaperTags(Vec<WallpaperTag>),
+    /// Printed as is, including its line breaks
+    Text(String),
+    Done,
+}
+
+fn json_text<T: Serialize>(data: T) -> String {
+    match serde_json::to_string(&Response::new(data)) {
+        Ok(v) => format!("{v}\n"),
+        Err(e) => {
+            log::error!("Failed to serialize output: {e}");
+            String::new()
+        }
+    }
+}
+
+fn wallpapers_text(wallpapers: &[Wallpaper]) -> String {
+    wallpapers
+        .iter()
+        .map(|w| {
+            let favorite = if w.is_favorite { "*" } else { "" };
+
+            format!("{}\t{}{favorite}\n", w.id, w.path)
+        })
+        .collect()
+}
+
+/// One block per cluster, copies show the id of the wallpaper they copy.
+fn duplicates_text(clusters: &[DuplicateCluster]) -> String {
+    let mut text = String::new();
+
+    for (i, cluster) in clusters.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+
+        for w in &cluster.wallpapers {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                w.id,
+                w.resolution.as_deref().unwrap_or("?"),
+                w.file_size.unwrap_or(0),
+                w.path
+            ));
+        }
+
+        for c in &cluster.copies {
+            text.push_str(&format!(
+                "{}\tcopy\t{}\t{}\n",
+                c.wallpaper_id,
+                c.file_size.unwrap_or(0),
+                c.path
+            ));
+        }
+    }
+
+    text
+}
+
+fn sources_text(sources: &[WallpaperSource]) -> String {
+    sources
+        .iter()
+        .map(|s| {
+            let state = if s.active { "enabled" } else { "disabled" };
+
+            format!("{}\t{}\t{state}\n", s.id, s.path)
+        })
+        .collect()
+}
+
+impl Output {
+    /// What the subcommand prints, as text or as JSON.
+    fn render(&self, json: bool) -> String {
+        match self {
+            Self::Wallpapers(v) if json => json_text(v),
+            Self::Wallpapers(v) => wallpapers_text(v),
+            Self::Page(v) if json => json_text(v),
+            Self::Page(v) => {
+                let mut text = wallpapers_text(&v.wallpapers);
+
+                if let Some(cursor) = &v.next_cursor {
+                    text.push_str(&format!(
+                        "# {} in total, next page: --cursor {cursor}\n",
+                        v.total
+                    ));
+                }
+
+                text
+            }
+            Self::Wallpaper(v) if json => json_text(v),
+            Self::Wallpaper(v) => wallpapers_text(std::slice::from_ref(v.as_ref())),
+            Self::Sources(v) if json => json_text(v),
+            Self::Sources(v) => sources_text(v),
+            Self::Source(v) if json => json_text(v),
+            Self::Source(v) => sources_text(std::slice::from_ref(v)),
+            Self::Screens(v) if json => json_text(v),
+            Self::Screens(v) => v.iter().map(|screen| format!("{screen}\n")).collect(),
+            Self::Active(v) if json => json_text(v),
+            Self::Active(v) => v
+                .iter()
+                .map(|a| format!("{}\t{}\t{}\n", a.screen, a.wallpaper_id, a.mode))
+                .collect(),
+            Self::Settings(v) if json => json_text(v),
+            Self::Settings(v) => v
+                .iter()
+                .map(|(key, value)| format!("{key}\t{value}\n"))
+                .collect(),
+            Self::Rotations(v) if json => json_text(v),
+            Self::Rotations(v) => v
+                .iter()
+                .map(|r| {
+                    let order = if r.shuffle { "shuffle" } else { "sequential" };
+                    let state = if r.enabled { "enabled" } else { "disabled" };
+                    let source = match (&r.collection_id, &r.wallpaper_source_id) {
+                        (Some(id), _) => format!("collection:{id}"),
+                        (None, Some(id)) => id.clone(),
+                        (None, None) => String::from("*"),
+                    };
+
+                    format!(
+                        "{}\t{}s\t{source}\t{order}\t{}\t{state}\n",
+                        r.screen, r.interval_secs, r.mode
+                    )
+                })
+                .collect(),
+            Self::History(v) if json => json_text(v),
+            Self::History(v) => v
+                .iter()
+                .map(|h| {
+                    format!(
+                        "{}\t{}\t{}\t{}\t{}\n",
+                        h.created_at, h.screen, h.wallpaper_id, h.mode, h.origin
+                    )
+                })
+                .collect(),
+            Self::Duplicates(v) if json => json_text(v),
+            Self::Duplicates(v) => duplicates_text(v),
+            Self::Collections(v) if json => json_text(v),
+            Self::Collections(v) => v
+                .iter()
+                .map(|c| format!("{}\t{}\n", c.id, c.name))
+                .collect(),
+            Self::Tags(v) if json => json_text(v),
+            Self::Tags(v) => v
+                .iter()
+                .map(|t| format!("{}\t{}\n", t.count, t.name))
+                .collect(),
+            Self::WallpaperTags(v) if json => json_text(v),
+            Self::WallpaperTags(v) => v
+                .iter()
+                .map(|t| format!("{}\t{}\n", t.name, t.origin))
+                .collect(),
+            Self::Text(v) if json => json_text(v),
+            Self::Text(v) => v.clone(),
+            Self::Done if json => json_text(true),
+            Self::Done => String::new(),
+        }
+    }
+
+    fn print(&self, json: bool) {
+        print!("{}", self.render(json));
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: crate::error::ErrorBody,
+}
+
+/// The `{"error": ...}` line printed for `--json`.
+pub fn error_json(error: &Error) -> String {
+    match serde_json::to_string(&ErrorResponse {
+        error: error.body(),
+    }) {
+        Ok(v) => format!("{v}\n"),
+        Err(e) => {
+            log::error!("Failed to serialize error: {e}");
+            String::new()
+        }
+    }
+}
+
+/// On stdout for `--json`, on stderr otherwise whatever the log level.
+pub fn print_error(error: &Error, json: bool) {
+    if json {
+        print!("{}", error_json(error));
+    } else {
+        eprintln!("{error}");
+    }
+}
+
+/// Sets a file that isn't part of any source, it can't be stored as active.
+fn set_unknown_path(
+    conn: &mut SqliteConnection,
+    screen: &str,
+    path: &str,
+    mode: String,
+) -> Result<(), Error> {
+    if !std::path::Path::new(path).is_file() {
+        return Err(Error::NotFound(format!(
+            "No wallpaper with id or path '{path}'"
+        )));
+    }
+
+    log::warn!("'{path}' isn't in any source, it won't be restored");
+
+    let backend = backend::get_backend(conn)?;
+    let h_mode = backend::Mode::from_string(&mode)?;
+
+    // Slices are cached by signature, which only scanned wallpapers have
+    if h_mode == backend::Mode::Span {
+        return Err(Error::InvalidArgument(String::from(
+            "Spanning needs a wallpaper from a source, scan it first",
+        )));
+    }
+
+    let screens = backend::expand_screen(backend.as_ref(), screen)?;
+    let assignments: Vec<Assignment> = screens
+        .into_iter()
+        .map(|x| Assignment::new(x, path.to_string(), h_mode.clone()))
+        .collect();
+
+    let errors: Vec<String> = backend
+        .set_batch(&assignments)?
+        .into_iter()
+        .filter_map(|r| r.result.err().map(|e| format!("{}: {e}", r.screen)))
+        .collect();
+
+    if !errors.is_empty() {
+        return Err(Error::ScreensFailed(errors));
+    }
+
+    Ok(())
+}
+
+fn execute(command: Command, conn: &mut SqliteConnection) -> Result<Output, Error> {
+    let output = match command {
+        Command::Set {
+            screen,
+            wallpaper,
+            mode,
+            temporary,
+        } => match commands::find_wallpaper(conn, &wallpaper)? {
+            Some(w) => {
+                commands::apply_wallpaper(conn, &screen, &w, mode, temporary, Origin::Manual)?;
+                Output::Wallpaper(Box::new(w))
+            }
+            None => {
+                set_unknown_path(conn, &screen, &wallpaper, mode)?;
+                Output::Done
+            }
+        },
+        Command::Random {
+            favorites,
+            collection,
+            screen,
+            mode,
+        } => {
+            let collection = match collection {
+                Some(v) => Some(commands::find_collection(conn, &v)?.id),
+                None => None,
+            };
+            let w = commands::pick_random_wallpaper(conn, favorites, collection.as_deref())?;
+            commands::apply_wallpaper(conn, &screen, &w, mode, false, Origin::Random)?;
+            Output::Wallpaper(Box::new(w))
+        }
+        Command::List { target, filter } => match target {
+            ListTarget::Wallpapers => Output::Wallpapers(commands::get_wallpapers(conn, &filter)?),
+            ListTarget::Sources => Output::Sources(commands::get_wallpaper_sources(conn)?),
+            ListTarget::Screens => Output::Screens(commands::get_screens(conn)?),
+            ListTarget::Active => Output::Active(commands::get_active_wallpapers(conn)?),
+        },
+        Command::Search { search } => Output::Page(commands::search_wallpapers(conn, &search)?),
+        Command::Source { action } => Output::Source(match action {
+            SourceAction::Add { path } => {
+                let path = match std::fs::canonicalize(&path) {
+                    Ok(v) => v.to_string_lossy().to_string(),
+                    Err(e) => return Err(Error::InvalidArgument(format!("{path}: {e}"))),
+                };
+
+                commands::add_wallpaper_source(conn, path)?
+            }
+            SourceAction::Remove { id } => commands::remove_wallpaper_source(conn, id)?,
+            SourceAction::Enable { id } => {
+                commands::update_wallpaper_source_active(conn, id, true)?
+            }
+            SourceAction::Disable { id } => {
+                commands::update_wallpaper_source_active(conn, id, false)?
+            }
+        }),
+        Command::Scan { source } => Output::Wallpapers(async_runtime::block_on(async {
+            let mut ctx = ScanContext::new(&ScanControl::default(), |progress| {
+                log::debug!("{progress:?}");
+            });
+
+            match source {
+                Some(id) => commands::scan_source(conn, id, &mut ctx).await,
+                None => commands::scan_all_sources(conn, &mut ctx).await,
+            }
+        })?),
+        Command::Favorite { id, unset } => Output::Wallpaper(Box::new(
+            commands::update_wallpaper_favorite(conn, id, !unset)?,
+        )),
+        Command::Restore => {
+            restore(conn)?;
+            Output::Done
+        }
+        Command::Setting { action } => match action {
+            SettingAction::List => {
+                Output::Settings(commands::get_settings(conn)?.into_iter().collect())
+            }
+            SettingAction::Set { key, value } => {
+                let value = commands::set_setting(conn, key.clone(), value)?;
+                Output::Settings(BTreeMap::from([(key, value)]))
+            }
+        },
+        Command::Rotation { action } => match action {
+            RotationAction::List => Output::Rotations(commands::get_rotations(conn)?),
+            RotationAction::Set {
+                screen,
+                interval,
+                source,
+                collection,
+                favorites,
+                sequential,
+                mode,
+                disabled,
+            } => {
+                let collection = match collection {
+                    Some(v) => Some(commands::find_collection(conn, &v)?.id),
+                    None => None,
+                };
+                let rotation = NewRotation::new(
+                    screen,
+                    interval,
+                    source,
+                    collection,
+                    favorites,
+                    !sequential,
+                    mode,
+                    !disabled,
+                );
+
+                Output::Rotations(vec![commands::set_rotation(conn, rotation)?])
+            }
+            RotationAction::Next { screen } => {
+                Output::Wallpaper(Box::new(commands::advance_rotation(conn, screen)?))
+            }
+            RotationAction::Remove { screen } => {
+                Output::Rotations(vec![commands::remove_rotation(conn, screen)?])
+            }
+        },
+        Command::History { action } => match action {
+            HistoryAction::List { screen, limit } => {
+                Output::History(commands::get_history(conn, screen, limit)?)
+            }
+            HistoryAction::Back { screen } => {
+                Output::Wallpapers(commands::step_history(conn, screen, Direction::Back)?)
+            }
+            HistoryAction::Forward { screen } => {
+                Output::Wallpapers(commands::step_history(conn, screen, Direction::Forward)?)
+            }
+        },
+        Command::Duplicates { max_distance } => {
+            Output::Duplicates(commands::get_duplicates(conn, max_distance)?)
+        }
+        Command::Collection { action } => collection_output(conn, action)?,
+        Command::Tag { action } => tag_output(conn, action)?,
+        Command::Config { action } => config_output(action)?,
+    };
+
+    Ok(output)
+}
+
+/// Looks wallpapers up by id or path, they have to be scanned.
+fn wallpaper_ids(conn: &mut SqliteConnection, wallpapers: &[String]) -> Result<Vec<String>, Error> {
+    wallpapers
+        .iter()
+        .map(|v| match commands::find_wallpaper(conn, v)? {
+            Some(w) => Ok(w.id),
+            None => Err(Error::NotFound(format!(
+                "No wallpaper with id or path '{v}'"
+            ))),
+        })
+        .collect()
+}
+
+fn collection_output(
+    conn: &mut SqliteConnection,
+    action: CollectionAction,
+) -> Result<Output, Error> {
+    let output = match action {
+        CollectionAction::List => Output::Collections(commands::get_collections(conn)?),
+        CollectionAction::Show { collection } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            Output::Wallpapers(commands::get_collection_wallpapers(conn, &id)?)
+        }
+        CollectionAction::Create { name } => {
+            Output::Collections(vec![commands::create_collection(conn, name)?])
+        }
+        CollectionAction::Rename { collection, name } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            Output::Collections(vec![commands::rename_collection(conn, id, name)?])
+        }
+        CollectionAction::Delete { collection } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            Output::Collections(vec![commands::remove_collection(conn, id)?])
+        }
+        CollectionAction::Add {
+            collection,
+            wallpapers,
+        } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            let wallpaper_ids = wallpaper_ids(conn, &wallpapers)?;
+            Output::Wallpapers(commands::add_to_collection(conn, id, wallpaper_ids)?)
+        }
+        CollectionAction::Remove {
+            collection,
+            wallpapers,
+        } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            let wallpaper_ids = wallpaper_ids(conn, &wallpapers)?;
+            Output::Wallpapers(commands::remove_from_collection(conn, id, wallpaper_ids)?)
+        }
+        CollectionAction::Move {
+            collection,
+            wallpaper,
+            position,
+        } => {
+            let id = commands::find_collection(conn, &collection)?.id;
+            let wallpaper_id = wallpaper_ids(conn, &[wallpaper])?.remove(0);
+            let mut order: Vec<String> = commands::get_collection_wallpapers(conn, &id)?
+                .into_iter()
+                .map(|w| w.id)
+                .collect();
+
+            let Some(from) = order.iter().position(|v| *v == wallpaper_id) else {
+                return Err(Error::NotFound(format!(
+                    "'{wallpaper_id}' is not in collection '{collection}'"
+                )));
+            };
+
+            let moved = order.remove(from);
+            order.insert(position.min(order.len()), moved);
+
+            Output::Wallpapers(commands::reorder_collection(conn, id, order)?)
+        }
+    };
+
+    Ok(output)
+}
+
+fn tag_output(conn: &mut SqliteConnection, action: TagAction) -> Result<Output, Error> {
+    let output = match action {
+        TagAction::List => Output::Tags(commands::get_tags(conn)?),
+        TagAction::Show { wallpaper } => {
+            let id = wallpaper_ids(conn, &[wallpaper])?.remove(0);
+            Output::WallpaperTags(commands::get_wallpaper_tags(conn, &id)?)
+        }
+        TagAction::Add { tags, wallpapers } => {
+            let wallpaper_ids = wallpaper_ids(conn, &wallpapers)?;
+            Output::Wallpapers(commands::add_tags(conn, wallpaper_ids, tags)?)
+        }
+        TagAction::Remove { tags, wallpapers } => {
+            let wallpaper_ids = wallpaper_ids(conn, &wallpapers)?;
+            Output::Wallpapers(commands::remove_tags(conn, wallpaper_ids, tags)?)
+        }
+    };
+
+    Ok(output)
+}
+
+fn config_output(action: ConfigAction) -> Result<Output, Error> {
+    let output = match action {
+        ConfigAction::PrintDefault => Output::Text(config::DEFAULT_CONFIG.to_string()),
+        ConfigAction::Check => {
+            let path = config::path();
+            config::load(&path)?;
+            Output::Text(format!("{}: ok\n", path.to_string_lossy()))
+        }
+    };
+
+    Ok(output)
+}
+
+/// `config` subcommands, these run before the configuration is loaded and without a database.
+pub fn run_config(action: ConfigAction, json: bool) -> Result<(), Error> {
+    config_output(action)?.print(json);
+
+    Ok(())
+}
+
+/// Runs a subcommand against the database without starting the GUI.
+pub fn run(command: Command, pool: &DbPool, json: bool) -> Result<(), Error> {
+    let mut conn = pool.get()?;
+
+    let output = execute(command, &mut conn);
+    // Hooks outlive the CLI otherwise, without their timeout
+    hooks::wait();
+
+    output?.print(json);
+
+    Ok(())
+}
+
+/// Runs a command forwarded to the GUI, see `utils::instance`, and returns what it prints.
+pub fn render(command: Command, pool: &DbPool, json: bool) -> Result<String, Error> {
+    let mut conn = pool.get()?;
+
+    Ok(execute(command, &mut conn)?.render(json))
+}
@@ -0,0 +1,413 @@
+use crate::backend;
+use crate::database::models::*;
+use crate::database::search;
+use crate::database::settings;
+use crate::database::tags::Query;
+use crate::error::Error;
+use crate::ipc::{Orientation, WallpaperFilter, WallpaperSearch};
+use crate::schema;
+use crate::utils::phash;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::sqlite::Sqlite;
+use std::collections::{HashMap, HashSet};
+
+/// Page size of `search_wallpapers` without a limit.
+const DEFAULT_PAGE_SIZE: u32 = 100;
+const MAX_PAGE_SIZE: u32 = 1000;
+
+pub fn get_screens(conn: &mut SqliteConnection) -> Result<Vec<String>, Error> {
+    match backend::get_backend(conn)?.list_outputs() {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn get_wallpaper_sources(conn: &mut SqliteConnection) -> Result<Vec<WallpaperSource>, Error> {
+    match schema::wallpaper_sources::table.get_results::<WallpaperSource>(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Wallpapers of every active source that match `filter`, unordered.
+fn filtered_wallpapers(
+    filter: &WallpaperFilter,
+) -> Result<schema::wallpapers::BoxedQuery<'static, Sqlite>, Error> {
+    let mut query = schema::wallpapers::table
+        .filter(
+            schema::wallpapers::wallpaper_source_id.eq_any(
+                schema::wallpaper_sources::table
+                    .filter(schema::wallpaper_sources::active.eq(true))
+                    .select(schema::wallpaper_sources::id),
+            ),
+        )
+        .into_boxed();
+
+    if let Some(v) = filter.min_width {
+        query = query.filter(schema::wallpapers::width.ge(v));
+    }
+
+    if let Some(v) = filter.max_width {
+        query = query.filter(schema::wallpapers::width.le(v));
+    }
+
+    if let Some(v) = filter.min_height {
+        query = query.filter(schema::wallpapers::height.ge(v));
+    }
+
+    if let Some(v) = filter.max_height {
+        query = query.filter(schema::wallpapers::height.le(v));
+    }
+
+    if let Some(v) = filter.min_aspect_ratio {
+        query = query.filter(schema::wallpapers::aspect_ratio.ge(v));
+    }
+
+    if let Some(v) = filter.max_aspect_ratio {
+        query = query.filter(schema::wallpapers::aspect_ratio.le(v));
+    }
+
+    if let Some(v) = filter.min_file_size {
+        query = query.filter(schema::wallpapers::file_size.ge(v));
+    }
+
+    if let Some(v) = filter.max_file_size {
+        query = query.filter(schema::wallpapers::file_size.le(v));
+    }
+
+    // A cleared search field sends an empty query
+    if let Some(v) = filter.tags.as_deref().filter(|v| !v.trim().is_empty()) {
+        query = query.filter(v.parse::<Query>()?.condition());
+    }
+
+    Ok(query)
+}
+
+/// Wallpapers of every active source that match `filter`.
+pub fn get_wallpapers(
+    conn: &mut SqliteConnection,
+    filter: &WallpaperFilter,
+) -> Result<Vec<Wallpaper>, Error> {
+    match filtered_wallpapers(filter)?.get_results::<Wallpaper>(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Wallpapers of every active source that match `search`, without the cursor.
+fn searched_wallpapers(
+    search: &WallpaperSearch,
+) -> Result<schema::wallpapers::BoxedQuery<'static, Sqlite>, Error> {
+    let mut query = filtered_wallpapers(&search.filter)?;
+
+    if let Some(v) = search.text.as_deref().and_then(search::text_query) {
+        query = query.filter(search::text_condition(v));
+    }
+
+    if let Some(v) = &search.source_id {
+        query = query.filter(schema::wallpapers::wallpaper_source_id.eq(v.clone()));
+    }
+
+    if search.favorites {
+        query = query.filter(schema::wallpapers::is_favorite.eq(true));
+    }
+
+    if let Some(v) = &search.resolution {
+        query = query.filter(schema::wallpapers::resolution.eq(v.clone()));
+    }
+
+    if let Some(v) = &search.category {
+        query = query.filter(schema::wallpapers::category.eq(v.clone()));
+    }
+
+    query = match search.orientation {
+        Some(Orientation::Landscape) => {
+            query.filter(schema::wallpapers::width.gt(schema::wallpapers::height))
+        }
+        Some(Orientation::Portrait) => {
+            query.filter(schema::wallpapers::width.lt(schema::wallpapers::height))
+        }
+        Some(Orientation::Square) => {
+            query.filter(schema::wallpapers::width.eq(schema::wallpapers::height))
+        }
+        None => query,
+    };
+
+    Ok(query)
+}
+
+/// One page of the wallpapers that match `search`, starting after its cursor.
+pub fn search_wallpapers(
+    conn: &mut SqliteConnection,
+    search: &WallpaperSearch,
+) -> Result<WallpaperPage, Error> {
+    let limit = search
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE) as usize;
+    let order = search::Order::new(search.sort, search.descending, search.favorites_first);
+
+    let total = searched_wallpapers(search)?
+        .count()
+        .get_result::<i64>(conn)?;
+
+    let mut query = searched_wallpapers(search)?;
+
+    if let Some(cursor) = &search.cursor {
+        query = query.filter(order.after(cursor)?);
+    }
+
+    // One more than needed tells whether there is a next page
+    let mut wallpapers = query
+        .order(sql::<Text>(&order.sql()))
+        .limit(limit as i64 + 1)
+        .get_results::<Wallpaper>(conn)?;
+
+    let next_cursor = if wallpapers.len() > limit {
+        wallpapers.truncate(limit);
+        wallpapers.last().map(|w| order.cursor(w))
+    } else {
+        None
+    };
+
+    Ok(WallpaperPage {
+        wallpapers,
+        total,
+        next_cursor,
+    })
+}
+
+/// Looks a wallpaper up by id first, then by path.
+pub fn find_wallpaper(
+    conn: &mut SqliteConnection,
+    id_or_path: &str,
+) -> Result<Option<Wallpaper>, Error> {
+    match schema::wallpapers::table
+        .find(id_or_path)
+        .get_result::<Wallpaper>(conn)
+        .optional()
+    {
+        Ok(Some(v)) => return Ok(Some(v)),
+        Ok(None) => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let path = match std::fs::canonicalize(id_or_path) {
+        Ok(v) => v.to_string_lossy().to_string(),
+        Err(_) => id_or_path.to_string(),
+    };
+
+    match schema::wallpapers::table
+        .filter(schema::wallpapers::path.eq(path))
+        .first::<Wallpaper>(conn)
+        .optional()
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn get_active_wallpapers(conn: &mut SqliteConnection) -> Result<Vec<Active>, Error> {
+    match schema::active::table.get_results::<Active>(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn get_settings(conn: &mut SqliteConnection) -> Result<HashMap<String, String>, Error> {
+    match settings::get_settings(conn) {
+        Ok(v) => Ok(v.into_iter().collect()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn get_rotations(conn: &mut SqliteConnection) -> Result<Vec<Rotation>, Error> {
+    match schema::rotations::table.get_results::<Rotation>(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn get_collections(conn: &mut SqliteConnection) -> Result<Vec<Collection>, Error> {
+    match schema::collections::table
+        .order(schema::collections::name.asc())
+        .get_results::<Collection>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Looks a collection up by id first, then by name.
+pub fn find_collection(conn: &mut SqliteConnection, id_or_name: &str) -> Result<Collection, Error> {
+    match schema::collections::table
+        .filter(
+            schema::collections::id
+                .eq(id_or_name)
+                .or(schema::collections::name.eq(id_or_name)),
+        )
+        .order(schema::collections::id.eq(id_or_name).desc())
+        .first::<Collection>(conn)
+        .optional()
+    {
+        Ok(Some(v)) => Ok(v),
+        Ok(None) => Err(Error::NotFound(format!(
+            "No collection with id or name '{id_or_name}'"
+        ))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Wallpapers of a collection in their order.
+pub fn get_collection_wallpapers(
+    conn: &mut SqliteConnection,
+    collection_id: &str,
+) -> Result<Vec<Wallpaper>, Error> {
+    // An unknown collection is an error, not an empty one
+    schema::collections::table
+        .find(collection_id)
+        .get_result::<Collection>(conn)?;
+
+    match schema::wallpapers::table
+        .inner_join(schema::collection_wallpapers::table)
+        .filter(schema::collection_wallpapers::collection_id.eq(collection_id))
+        .order(schema::collection_wallpapers::position.asc())
+        .select(schema::wallpapers::all_columns)
+        .get_results::<Wallpaper>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Every tag with the number of wallpapers that have it, most used first.
+pub fn get_tags(conn: &mut SqliteConnection) -> Result<Vec<TagCount>, Error> {
+    let count = diesel::dsl::count(schema::wallpaper_tags::wallpaper_id);
+
+    match schema::tags::table
+        .inner_join(schema::wallpaper_tags::table)
+        .group_by(schema::tags::name)
+        .select((schema::tags::name, count))
+        .order((count.desc(), schema::tags::name.asc()))
+        .get_results::<TagCount>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Tags of one wallpaper with their origin, by name.
+pub fn get_wallpaper_tags(
+    conn: &mut SqliteConnection,
+    wallpaper_id: &str,
+) -> Result<Vec<WallpaperTag>, Error> {
+    schema::wallpapers::table
+        .find(wallpaper_id)
+        .get_result::<Wallpaper>(conn)?;
+
+    match schema::tags::table
+        .inner_join(schema::wallpaper_tags::table)
+        .filter(schema::wallpaper_tags::wallpaper_id.eq(wallpaper_id))
+        .order(schema::tags::name.asc())
+        .select((schema::tags::name, schema::wallpaper_tags::origin))
+        .get_results::<WallpaperTag>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Newest entries first, optionally only those of `screen`. At most [`MAX_PAGE_SIZE`] of them,
+/// like a search page.
+pub fn get_history(
+    conn: &mut SqliteConnection,
+    screen: Option<String>,
+    limit: i64,
+) -> Result<Vec<History>, Error> {
+    // SQLite reads a negative limit as none at all
+    if limit <= 0 {
+        return Err(Error::InvalidArgument(format!(
+            "limit {limit} is not a positive number"
+        )));
+    }
+
+    let mut query = schema::history::table.into_boxed();
+
+    if let Some(screen) = screen {
+        query = query.filter(schema::history::screen.eq(screen));
+    }
+
+    match query
+        .order(schema::history::id.desc())
+        .limit(limit.min(MAX_PAGE_SIZE as i64))
+        .get_results::<History>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Clusters of wallpapers whose perceptual hashes are at most `max_distance` bits apart,
+/// and wallpapers with exact copies. Biggest clusters first.
+pub fn get_duplicates(
+    conn: &mut SqliteConnection,
+    max_distance: u32,
+) -> Result<Vec<DuplicateCluster>, Error> {
+    if max_distance > 64 {
+        return Err(Error::InvalidArgument(format!(
+            "max distance {max_distance} is above 64 bits"
+        )));
+    }
+
+    let wallpapers = schema::wallpapers::table.get_results::<Wallpaper>(conn)?;
+    let mut copies: HashMap<String, Vec<Duplicate>> = HashMap::new();
+
+    for copy in schema::duplicates::table.get_results::<Duplicate>(conn)? {
+        copies
+            .entry(copy.wallpaper_id.clone())
+            .or_default()
+            .push(copy);
+    }
+
+    // Wallpapers that aren't hashed yet can still have exact copies
+    let hashed: Vec<&Wallpaper> = wallpapers.iter().filter(|w| w.phash.is_some()).collect();
+    let hashes: Vec<i64> = hashed.iter().filter_map(|w| w.phash).collect();
+
+    let mut groups: Vec<Vec<&Wallpaper>> = phash::clusters(&hashes, max_distance)
+        .into_iter()
+        .map(|g| g.into_iter().map(|i| hashed[i]).collect())
+        .collect();
+
+    let clustered: HashSet<&str> = groups.iter().flatten().map(|w| w.id.as_str()).collect();
+
+    groups.extend(
+        wallpapers
+            .iter()
+            .filter(|w| copies.contains_key(&w.id) && !clustered.contains(w.id.as_str()))
+            .map(|w| vec![w]),
+    );
+
+    let mut clusters: Vec<DuplicateCluster> = groups
+        .into_iter()
+        .map(|mut group| {
+            group.sort_by_key(|w| {
+                let pixels = i64::from(w.width.unwrap_or(0)) * i64::from(w.height.unwrap_or(0));
+                std::cmp::Reverse((pixels, w.file_size.unwrap_or(0)))
+            });
+
+            DuplicateCluster {
+                copies: group
+                    .iter()
+                    .flat_map(|w| copies.remove(&w.id).unwrap_or_default())
+                    .collect(),
+                wallpapers: group.into_iter().cloned().collect(),
+            }
+        })
+        .collect();
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.wallpapers.len() + c.copies.len()));
+
+    Ok(clusters)
+}
@@ -0,0 +1,11 @@
+//! Logic shared by the `#[tauri::command]` wrappers in `ipc` and the headless CLI.
+
+mod get;
+mod remove;
+mod set;
+mod util;
+
+pub use get::*;
+pub use remove::*;
+pub use set::*;
+pub use util::*;
@@ -0,0 +1,84 @@
+use crate::commands::get_collection_wallpapers;
+use crate::database::models::*;
+use crate::database::tags;
+use crate::error::Error;
+use crate::schema;
+use diesel::prelude::*;
+
+pub fn remove_wallpaper_source(
+    conn: &mut SqliteConnection,
+    id: String,
+) -> Result<WallpaperSource, Error> {
+    match diesel::delete(
+        schema::wallpaper_sources::table.filter(schema::wallpaper_sources::id.eq(id)),
+    )
+    .get_result(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn remove_rotation(conn: &mut SqliteConnection, screen: String) -> Result<Rotation, Error> {
+    match diesel::delete(schema::rotations::table.find(screen)).get_result(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn remove_collection(conn: &mut SqliteConnection, id: String) -> Result<Collection, Error> {
+    match diesel::delete(schema::collections::table.find(id)).get_result(conn) {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Takes wallpapers out of a collection, returns what's left in order.
+pub fn remove_from_collection(
+    conn: &mut SqliteConnection,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Vec<Wallpaper>, Error> {
+    diesel::delete(
+        schema::collection_wallpapers::table
+            .filter(schema::collection_wallpapers::collection_id.eq(&collection_id))
+            .filter(schema::collection_wallpapers::wallpaper_id.eq_any(&wallpaper_ids)),
+    )
+    .execute(conn)?;
+
+    get_collection_wallpapers(conn, &collection_id)
+}
+
+/// Takes the tags off each wallpaper whatever their origin, a scan doesn't bring them back.
+/// Tags no wallpaper has anymore are deleted. Returns the wallpapers with their keywords
+/// updated.
+pub fn remove_tags(
+    conn: &mut SqliteConnection,
+    wallpaper_ids: Vec<String>,
+    names: Vec<String>,
+) -> Result<Vec<Wallpaper>, Error> {
+    let names = names
+        .iter()
+        .map(|v| tags::normalize(v))
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    conn.transaction::<_, Error, _>(|conn| {
+        for wallpaper_id in &wallpaper_ids {
+            if tags::detach(conn, wallpaper_id, &names)? > 0 {
+                tags::sync_keywords(conn, wallpaper_id)?;
+            }
+        }
+
+        tags::remove_unused(conn)?;
+
+        Ok(())
+    })?;
+
+    match schema::wallpapers::table
+        .filter(schema::wallpapers::id.eq_any(&wallpaper_ids))
+        .get_results::<Wallpaper>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
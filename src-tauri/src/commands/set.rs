@@ -0,0 +1,818 @@
+use crate::backend::{self, Assignment, BackendKind, Prescale, SwwwTransition};
+use crate::commands::get_collection_wallpapers;
+use crate::database::history::{self, Direction, Origin};
+use crate::database::models::*;
+use crate::database::settings::{
+    set_setting as save_setting, BACKEND, HOOK_TIMEOUT, KNOWN_SETTINGS, THEME_EXPORT, WATCH_SOURCES,
+};
+use crate::database::tags;
+use crate::error::Error;
+use crate::ipc::{ScreenAssignment, ScreenResult};
+use crate::schema;
+use crate::utils::hooks::{self, Event};
+use crate::utils::{now, rotation, span, theme};
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::upsert::excluded;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+fn save_active(conn: &mut SqliteConnection, active: &NewActive) -> QueryResult<usize> {
+    diesel::insert_into(schema::active::table)
+        .values(active)
+        .on_conflict(schema::active::dsl::screen)
+        .do_update()
+        .set((
+            schema::active::dsl::wallpaper_id.eq(excluded(schema::active::dsl::wallpaper_id)),
+            schema::active::dsl::mode.eq(excluded(schema::active::dsl::mode)),
+        ))
+        .execute(conn)
+}
+
+/// Picks a random wallpaper from the active sources, or from a collection when
+/// `collection_id` is set.
+pub fn pick_random_wallpaper(
+    conn: &mut SqliteConnection,
+    favorites_only: bool,
+    collection_id: Option<&str>,
+) -> Result<Wallpaper, Error> {
+    let mut query = schema::wallpapers::table
+        .select(schema::wallpapers::all_columns)
+        .into_boxed();
+
+    match collection_id {
+        Some(id) => {
+            schema::collections::table
+                .find(id)
+                .get_result::<Collection>(conn)?;
+
+            query = query.filter(
+                schema::wallpapers::id.eq_any(
+                    schema::collection_wallpapers::table
+                        .filter(schema::collection_wallpapers::collection_id.eq(id))
+                        .select(schema::collection_wallpapers::wallpaper_id),
+                ),
+            );
+        }
+        None => {
+            query = query.filter(
+                schema::wallpapers::wallpaper_source_id.eq_any(
+                    schema::wallpaper_sources::table
+                        .filter(schema::wallpaper_sources::dsl::active.eq(true))
+                        .select(schema::wallpaper_sources::id),
+                ),
+            );
+        }
+    }
+
+    if favorites_only {
+        query = query.filter(schema::wallpapers::is_favorite.eq(true));
+    }
+
+    match query.get_results::<Wallpaper>(conn) {
+        Ok(v) => {
+            if v.is_empty() {
+                return Err(Error::NotFound(String::from("No wallpapers found")));
+            }
+
+            let mut rng = rand::rng();
+            let r = rng.random_range(0..v.len());
+
+            Ok(v[r].clone())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Sets `wallpaper` on `screen` (or `"all"`) and, unless temporary, stores it as active
+/// and records it in the history.
+pub fn apply_wallpaper(
+    conn: &mut SqliteConnection,
+    screen: &str,
+    wallpaper: &Wallpaper,
+    mode: String,
+    is_temporary: bool,
+    origin: Origin,
+) -> Result<(), Error> {
+    apply(
+        conn,
+        &[screen.to_string()],
+        wallpaper,
+        mode,
+        is_temporary,
+        Some(origin),
+    )
+}
+
+/// Same as [`apply_wallpaper`] on several screens at once, which a span is cut across.
+/// `origin: None` skips the history for back/forward steps.
+fn apply(
+    conn: &mut SqliteConnection,
+    screens: &[String],
+    wallpaper: &Wallpaper,
+    mode: String,
+    is_temporary: bool,
+    origin: Option<Origin>,
+) -> Result<(), Error> {
+    let h_mode = backend::Mode::from_string(&mode)?;
+    let backend = backend::get_backend(conn)?;
+
+    let screens = screens
+        .iter()
+        .map(|x| backend::expand_screen(backend.as_ref(), x))
+        .collect::<Result<Vec<Vec<String>>, _>>()?
+        .concat();
+
+    let assignments: Vec<Assignment> = match h_mode {
+        backend::Mode::Span => span::assignments(&wallpaper.path, &wallpaper.signature, &screens)?,
+        _ => screens
+            .into_iter()
+            .map(|x| Assignment::from_wallpaper(x, wallpaper, h_mode.clone()))
+            .collect(),
+    };
+
+    let results = backend.set_batch(&assignments)?;
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut applied = false;
+    let mut hook_envs: Vec<hooks::Env> = Vec::new();
+
+    for r in results {
+        match r.result {
+            Ok(_) => {
+                applied = true;
+
+                if !is_temporary {
+                    let active = NewActive::new(r.screen, wallpaper.id.clone(), h_mode.to_string());
+                    hook_envs.push(hooks::wallpaper_env(
+                        &active.screen,
+                        wallpaper,
+                        &active.mode,
+                    ));
+
+                    if let Err(e) = save_active(conn, &active) {
+                        return Err(e.into());
+                    }
+
+                    if let Some(origin) = origin {
+                        history::record(
+                            conn,
+                            &active.screen,
+                            &active.wallpaper_id,
+                            &active.mode,
+                            origin,
+                        )?;
+                    }
+                }
+            }
+            Err(e) => errors.push(format!("{}: {e}", r.screen)),
+        }
+    }
+
+    if applied && !is_temporary {
+        if let Err(e) = theme::export(conn, wallpaper) {
+            log::warn!("Failed to export theme: {e}");
+        }
+    }
+
+    let event = match origin {
+        Some(Origin::Rotation) => Event::Rotation,
+        _ => Event::WallpaperSet,
+    };
+    hooks::trigger(conn, event, hook_envs);
+
+    if let Err(e) = backend.unload(None) {
+        log::warn!("Failed to unload unused wallpapers: {e}");
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::ScreensFailed(errors));
+    }
+
+    Ok(())
+}
+
+/// Sets `wallpaper_id`, or a random wallpaper (from `collection_id` when set) when `None`.
+pub fn set_wallpaper(
+    conn: &mut SqliteConnection,
+    screen: String,
+    wallpaper_id: Option<String>,
+    collection_id: Option<String>,
+    mode: String,
+    is_temporary: bool,
+) -> Result<Wallpaper, Error> {
+    let origin = match wallpaper_id {
+        Some(_) => Origin::Manual,
+        None => Origin::Random,
+    };
+
+    let wallpaper = match wallpaper_id {
+        Some(id) => {
+            if id.is_empty() {
+                return Err(Error::InvalidArgument(String::from("Invalid wallpaper id")));
+            }
+
+            schema::wallpapers::table
+                .filter(schema::wallpapers::id.eq(&id))
+                .get_result::<Wallpaper>(conn)?
+        }
+        None => pick_random_wallpaper(conn, false, collection_id.as_deref())?,
+    };
+
+    apply_wallpaper(conn, &screen, &wallpaper, mode, is_temporary, origin)?;
+
+    Ok(wallpaper)
+}
+
+pub fn set_wallpapers(
+    conn: &mut SqliteConnection,
+    assignments: Vec<ScreenAssignment>,
+    is_temporary: bool,
+) -> Result<Vec<ScreenResult>, Error> {
+    let backend = backend::get_backend(conn)?;
+
+    let mut screen_results: Vec<ScreenResult> = Vec::new();
+    let mut batch: Vec<Assignment> = Vec::new();
+    // Backends may reorder results, so actives are matched back by screen
+    let mut actives: HashMap<String, NewActive> = HashMap::new();
+    // Screens spanning the same wallpaper are cut together
+    let mut spans: HashMap<String, (Wallpaper, Vec<String>)> = HashMap::new();
+    let mut wallpapers: HashMap<String, Wallpaper> = HashMap::new();
+
+    for assignment in assignments {
+        let h_mode = match backend::Mode::from_string(&assignment.mode) {
+            Ok(v) => v,
+            Err(e) => {
+                screen_results.push(ScreenResult::new(assignment.screen, Some(e.to_string())));
+                continue;
+            }
+        };
+
+        match schema::wallpapers::table
+            .find(&assignment.wallpaper_id)
+            .get_result::<Wallpaper>(conn)
+        {
+            Ok(v) => {
+                actives.insert(
+                    assignment.screen.clone(),
+                    NewActive::new(assignment.screen.clone(), v.id.clone(), h_mode.to_string()),
+                );
+                wallpapers.insert(v.id.clone(), v.clone());
+
+                match h_mode {
+                    backend::Mode::Span => {
+                        spans
+                            .entry(v.id.clone())
+                            .or_insert_with(|| (v, Vec::new()))
+                            .1
+                            .push(assignment.screen);
+                    }
+                    _ => batch.push(Assignment::from_wallpaper(assignment.screen, &v, h_mode)),
+                }
+            }
+            Err(e) => {
+                screen_results.push(ScreenResult::new(assignment.screen, Some(e.to_string())))
+            }
+        }
+    }
+
+    for (wallpaper, screens) in spans.into_values() {
+        match span::assignments(&wallpaper.path, &wallpaper.signature, &screens) {
+            Ok(v) => batch.extend(v),
+            Err(e) => screen_results.extend(
+                screens
+                    .into_iter()
+                    .map(|screen| ScreenResult::new(screen, Some(e.to_string()))),
+            ),
+        }
+    }
+
+    // Every screen is checked before the batch is sent, so that it applies as a whole
+    if !screen_results.is_empty() {
+        let aborted = backend::Error::Aborted.to_string();
+        screen_results.extend(
+            batch
+                .into_iter()
+                .map(|a| ScreenResult::new(a.screen, Some(aborted.clone()))),
+        );
+
+        return Ok(screen_results);
+    }
+
+    let results = backend.set_batch(&batch)?;
+    let mut themed: Option<String> = None;
+    let mut hook_envs: Vec<hooks::Env> = Vec::new();
+
+    for r in results {
+        match r.result {
+            Ok(_) => {
+                if let Some(active) = actives.get(&r.screen).filter(|_| !is_temporary) {
+                    themed.get_or_insert_with(|| active.wallpaper_id.clone());
+
+                    if let Some(wallpaper) = wallpapers.get(&active.wallpaper_id) {
+                        hook_envs.push(hooks::wallpaper_env(
+                            &active.screen,
+                            wallpaper,
+                            &active.mode,
+                        ));
+                    }
+
+                    if let Err(e) = save_active(conn, active) {
+                        return Err(e.into());
+                    }
+
+                    history::record(
+                        conn,
+                        &active.screen,
+                        &active.wallpaper_id,
+                        &active.mode,
+                        Origin::Manual,
+                    )?;
+                }
+
+                screen_results.push(ScreenResult::new(r.screen, None));
+            }
+            Err(e) => screen_results.push(ScreenResult::new(r.screen, Some(e.to_string()))),
+        }
+    }
+
+    // The theme follows the first screen that got its wallpaper
+    if let Some(id) = themed {
+        if let Err(e) = theme::export_by_id(conn, &id) {
+            log::warn!("Failed to export theme: {e}");
+        }
+    }
+
+    hooks::trigger(conn, Event::WallpaperSet, hook_envs);
+
+    if let Err(e) = backend.unload(None) {
+        log::warn!("Failed to unload unused wallpapers: {e}");
+    }
+
+    Ok(screen_results)
+}
+
+pub fn add_wallpaper_source(
+    conn: &mut SqliteConnection,
+    path: String,
+) -> Result<WallpaperSource, Error> {
+    let wallpaper_source = NewWallpaperSource::new(path);
+
+    match diesel::insert_into(schema::wallpaper_sources::table)
+        .values(&wallpaper_source)
+        .get_result::<WallpaperSource>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn update_wallpaper_source_active(
+    conn: &mut SqliteConnection,
+    id: String,
+    active: bool,
+) -> Result<WallpaperSource, Error> {
+    match diesel::update(schema::wallpaper_sources::table.find(id))
+        .set(schema::wallpaper_sources::active.eq(active))
+        .get_result(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn update_wallpaper_favorite(
+    conn: &mut SqliteConnection,
+    id: String,
+    new_value: bool,
+) -> Result<Wallpaper, Error> {
+    match diesel::update(schema::wallpapers::table)
+        .filter(schema::wallpapers::id.eq(&id))
+        .set(schema::wallpapers::dsl::is_favorite.eq(new_value))
+        .get_result(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set_setting(
+    conn: &mut SqliteConnection,
+    key: String,
+    value: String,
+) -> Result<String, Error> {
+    if key == BACKEND {
+        if let Err(e) = BackendKind::from_string(&value) {
+            return Err(Error::InvalidArgument(e.to_string()));
+        }
+    } else if key == WATCH_SOURCES || key == THEME_EXPORT || key == Prescale::SETTINGS[0] {
+        if value != "true" && value != "false" {
+            return Err(Error::InvalidArgument(format!(
+                "'{key}' must be 'true' or 'false'"
+            )));
+        }
+    } else if key == Prescale::SETTINGS[1] {
+        if let Err(e) = value.parse::<u64>() {
+            return Err(Error::InvalidArgument(format!(
+                "'{key}' must be a size in MiB: {e}"
+            )));
+        }
+    } else if key == HOOK_TIMEOUT {
+        if let Err(e) = value.parse::<u64>() {
+            return Err(Error::InvalidArgument(format!(
+                "'{key}' must be a number of seconds: {e}"
+            )));
+        }
+    } else if !KNOWN_SETTINGS.contains(&key.as_str())
+        && !SwwwTransition::SETTINGS.contains(&key.as_str())
+    {
+        return Err(Error::InvalidArgument(format!("Unknown setting '{key}'")));
+    }
+
+    match save_setting(conn, &key, &value) {
+        Ok(_) => Ok(value),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn set_rotation(conn: &mut SqliteConnection, rotation: NewRotation) -> Result<Rotation, Error> {
+    if rotation.interval_secs <= 0 {
+        return Err(Error::InvalidArgument(String::from(
+            "Rotation interval must be positive",
+        )));
+    }
+
+    backend::Mode::from_string(&rotation.mode)?;
+
+    if let Some(id) = &rotation.collection_id {
+        schema::collections::table
+            .find(id)
+            .get_result::<Collection>(conn)?;
+    }
+
+    // Changing the filters invalidates the current cycle
+    diesel::delete(
+        schema::rotation_played::table.filter(schema::rotation_played::screen.eq(&rotation.screen)),
+    )
+    .execute(conn)?;
+
+    match diesel::insert_into(schema::rotations::table)
+        .values(&rotation)
+        .on_conflict(schema::rotations::screen)
+        .do_update()
+        .set(&rotation)
+        .get_result::<Rotation>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn collection_name(name: &str) -> Result<String, Error> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(Error::InvalidArgument(String::from(
+            "Collection name can't be empty",
+        )));
+    }
+
+    Ok(name.to_string())
+}
+
+fn collection_error(e: DieselError, name: &str) -> Error {
+    match e {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            Error::InvalidArgument(format!("A collection named '{name}' already exists"))
+        }
+        e => e.into(),
+    }
+}
+
+pub fn create_collection(conn: &mut SqliteConnection, name: String) -> Result<Collection, Error> {
+    let collection = NewCollection::new(collection_name(&name)?, now());
+
+    match diesel::insert_into(schema::collections::table)
+        .values(&collection)
+        .get_result::<Collection>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(collection_error(e, &collection.name)),
+    }
+}
+
+pub fn rename_collection(
+    conn: &mut SqliteConnection,
+    id: String,
+    name: String,
+) -> Result<Collection, Error> {
+    let name = collection_name(&name)?;
+
+    match diesel::update(schema::collections::table.find(id))
+        .set(schema::collections::name.eq(&name))
+        .get_result::<Collection>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(collection_error(e, &name)),
+    }
+}
+
+/// Appends the wallpapers that aren't in the collection yet, returns its wallpapers in order.
+pub fn add_to_collection(
+    conn: &mut SqliteConnection,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Vec<Wallpaper>, Error> {
+    conn.transaction::<_, Error, _>(|conn| {
+        schema::collections::table
+            .find(&collection_id)
+            .get_result::<Collection>(conn)?;
+
+        let mut position = schema::collection_wallpapers::table
+            .filter(schema::collection_wallpapers::collection_id.eq(&collection_id))
+            .select(diesel::dsl::max(schema::collection_wallpapers::position))
+            .get_result::<Option<i32>>(conn)?
+            .map_or(0, |v| v + 1);
+
+        for wallpaper_id in &wallpaper_ids {
+            schema::wallpapers::table
+                .find(wallpaper_id)
+                .get_result::<Wallpaper>(conn)?;
+
+            position += diesel::insert_into(schema::collection_wallpapers::table)
+                .values((
+                    schema::collection_wallpapers::collection_id.eq(&collection_id),
+                    schema::collection_wallpapers::wallpaper_id.eq(wallpaper_id),
+                    schema::collection_wallpapers::position.eq(position),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)? as i32;
+        }
+
+        Ok(())
+    })?;
+
+    get_collection_wallpapers(conn, &collection_id)
+}
+
+/// Puts the collection in the order of `wallpaper_ids`, which lists each of its wallpapers once.
+pub fn reorder_collection(
+    conn: &mut SqliteConnection,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Vec<Wallpaper>, Error> {
+    let current: HashSet<String> = get_collection_wallpapers(conn, &collection_id)?
+        .into_iter()
+        .map(|w| w.id)
+        .collect();
+    let new: HashSet<&String> = wallpaper_ids.iter().collect();
+
+    if new.len() != wallpaper_ids.len()
+        || current.len() != new.len()
+        || !new.iter().all(|id| current.contains(*id))
+    {
+        return Err(Error::InvalidArgument(String::from(
+            "The new order must list every wallpaper of the collection once",
+        )));
+    }
+
+    conn.transaction::<_, Error, _>(|conn| {
+        for (position, wallpaper_id) in wallpaper_ids.iter().enumerate() {
+            diesel::update(
+                schema::collection_wallpapers::table.find((&collection_id, wallpaper_id)),
+            )
+            .set(schema::collection_wallpapers::position.eq(position as i32))
+            .execute(conn)?;
+        }
+
+        Ok(())
+    })?;
+
+    get_collection_wallpapers(conn, &collection_id)
+}
+
+/// Tags each wallpaper with every name, they're created as needed. Returns the wallpapers
+/// with their keywords updated.
+pub fn add_tags(
+    conn: &mut SqliteConnection,
+    wallpaper_ids: Vec<String>,
+    names: Vec<String>,
+) -> Result<Vec<Wallpaper>, Error> {
+    let names = names
+        .iter()
+        .map(|v| tags::normalize(v))
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    if names.is_empty() {
+        return Err(Error::InvalidArgument(String::from("No tags given")));
+    }
+
+    conn.transaction::<_, Error, _>(|conn| {
+        for wallpaper_id in &wallpaper_ids {
+            schema::wallpapers::table
+                .find(wallpaper_id)
+                .get_result::<Wallpaper>(conn)?;
+
+            tags::attach(conn, wallpaper_id, &names, tags::Origin::User)?;
+            tags::sync_keywords(conn, wallpaper_id)?;
+        }
+
+        Ok(())
+    })?;
+
+    match schema::wallpapers::table
+        .filter(schema::wallpapers::id.eq_any(&wallpaper_ids))
+        .get_results::<Wallpaper>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Skips to the next wallpaper of the rotation on `screen` without waiting for its interval.
+pub fn advance_rotation(conn: &mut SqliteConnection, screen: String) -> Result<Wallpaper, Error> {
+    let target = schema::rotations::table
+        .find(&screen)
+        .get_result::<Rotation>(conn)?;
+
+    match rotation::advance(conn, &target)? {
+        Some(v) => Ok(v),
+        None => Err(Error::NotFound(format!(
+            "Rotation for '{screen}' has no wallpapers"
+        ))),
+    }
+}
+
+/// Screens set together by a back/forward step.
+#[derive(Debug, PartialEq)]
+struct HistoryBatch {
+    screens: Vec<String>,
+    wallpaper_id: String,
+    mode: String,
+    /// Screens that stepped and the entry their position moves to
+    steps: Vec<(String, i32)>,
+}
+
+/// Groups the entries stepped to. Span entries of the same wallpaper are cut once across
+/// their screens and the other screens showing that span, like `hotplug` does for a new
+/// monitor, other entries are set on their screen alone.
+fn history_batches(entries: Vec<History>, active: &[Active]) -> Vec<HistoryBatch> {
+    let span = backend::Mode::Span.to_string();
+    let mut batches: Vec<HistoryBatch> = Vec::new();
+
+    for entry in entries {
+        let shared = batches
+            .iter_mut()
+            .find(|b| entry.mode == span && b.mode == span && b.wallpaper_id == entry.wallpaper_id);
+
+        if let Some(batch) = shared {
+            batch.screens.push(entry.screen.clone());
+            batch.steps.push((entry.screen, entry.id));
+            continue;
+        }
+
+        batches.push(HistoryBatch {
+            screens: vec![entry.screen.clone()],
+            wallpaper_id: entry.wallpaper_id,
+            mode: entry.mode,
+            steps: vec![(entry.screen, entry.id)],
+        });
+    }
+
+    for batch in batches.iter_mut().filter(|b| b.mode == span) {
+        for a in active {
+            if a.wallpaper_id == batch.wallpaper_id
+                && a.mode == span
+                && !batch.screens.contains(&a.screen)
+            {
+                batch.screens.push(a.screen.clone());
+            }
+        }
+    }
+
+    batches
+}
+
+/// Goes back or forward through the history of `screen`, `"all"` steps every screen.
+pub fn step_history(
+    conn: &mut SqliteConnection,
+    screen: String,
+    direction: Direction,
+) -> Result<Vec<Wallpaper>, Error> {
+    let screens = match screen.as_str() {
+        "all" => {
+            let backend = backend::get_backend(conn)?;
+            backend::expand_screen(backend.as_ref(), &screen)?
+        }
+        _ => vec![screen.clone()],
+    };
+
+    let mut entries: Vec<History> = Vec::new();
+
+    for screen in &screens {
+        if let Some(entry) = history::step(conn, screen, direction)? {
+            entries.push(entry);
+        }
+    }
+
+    let active = schema::active::table.get_results::<Active>(conn)?;
+    let mut wallpapers: Vec<Wallpaper> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    for batch in history_batches(entries, &active) {
+        let wallpaper = schema::wallpapers::table
+            .find(&batch.wallpaper_id)
+            .get_result::<Wallpaper>(conn)?;
+
+        match apply(conn, &batch.screens, &wallpaper, batch.mode, false, None) {
+            Ok(_) => {
+                for (screen, id) in &batch.steps {
+                    history::set_cursor(conn, screen, *id)?;
+                }
+
+                wallpapers.push(wallpaper);
+            }
+            Err(e) => errors.push(format!("{}: {e}", batch.screens.join(", "))),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(Error::ScreensFailed(errors));
+    }
+
+    if wallpapers.is_empty() {
+        return Err(Error::NotFound(format!(
+            "No {direction} wallpaper in the history of '{screen}'"
+        )));
+    }
+
+    Ok(wallpapers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i32, screen: &str, wallpaper_id: &str, mode: &str) -> History {
+        History {
+            id,
+            screen: screen.to_string(),
+            wallpaper_id: wallpaper_id.to_string(),
+            mode: mode.to_string(),
+            origin: Origin::Manual.to_string(),
+            created_at: 0,
+        }
+    }
+
+    fn active(screen: &str, wallpaper_id: &str, mode: &str) -> Active {
+        Active {
+            screen: screen.to_string(),
+            wallpaper_id: wallpaper_id.to_string(),
+            mode: mode.to_string(),
+        }
+    }
+
+    #[test]
+    fn step_back_to_span_cuts_it_once() {
+        let entries = vec![
+            entry(3, "DP-1", "a", "span"),
+            entry(4, "DP-2", "a", "span"),
+            entry(5, "HDMI-A-1", "b", "default"),
+        ];
+        // DP-3 still shows the span, HDMI-A-1 the same wallpaper in another mode
+        let active = [
+            active("DP-3", "a", "span"),
+            active("HDMI-A-1", "a", "cover"),
+        ];
+
+        let batches = history_batches(entries, &active);
+
+        assert_eq!(
+            batches,
+            [
+                HistoryBatch {
+                    screens: ["DP-1", "DP-2", "DP-3"].map(String::from).to_vec(),
+                    wallpaper_id: String::from("a"),
+                    mode: String::from("span"),
+                    steps: vec![(String::from("DP-1"), 3), (String::from("DP-2"), 4)],
+                },
+                HistoryBatch {
+                    screens: vec![String::from("HDMI-A-1")],
+                    wallpaper_id: String::from("b"),
+                    mode: String::from("default"),
+                    steps: vec![(String::from("HDMI-A-1"), 5)],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn same_wallpaper_other_modes_stay_apart() {
+        let entries = vec![
+            entry(1, "DP-1", "a", "cover"),
+            entry(2, "DP-2", "a", "cover"),
+        ];
+
+        assert_eq!(history_batches(entries, &[]).len(), 2);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::database::models::*;
+use crate::error::Error;
+use crate::schema;
+use crate::utils::hooks::{self, Event};
+use crate::utils::scan::{scan, scan_all, ScanContext};
+use diesel::prelude::*;
+
+pub async fn scan_source(
+    conn: &mut SqliteConnection,
+    source_id: String,
+    ctx: &mut ScanContext,
+) -> Result<Vec<Wallpaper>, Error> {
+    let result = match schema::wallpaper_sources::table
+        .find(source_id)
+        .get_result::<WallpaperSource>(conn)
+    {
+        Ok(v) => scan(conn, v.id, v.path, ctx).await,
+        Err(e) => Err(e.into()),
+    };
+
+    finish_scan(conn, ctx, result)
+}
+
+pub async fn scan_all_sources(
+    conn: &mut SqliteConnection,
+    ctx: &mut ScanContext,
+) -> Result<Vec<Wallpaper>, Error> {
+    let result = scan_all(conn, ctx).await;
+
+    finish_scan(conn, ctx, result)
+}
+
+/// Reports the end of the run and triggers the scan hook, failed scans included.
+fn finish_scan(
+    conn: &mut SqliteConnection,
+    ctx: &mut ScanContext,
+    result: Result<Vec<Wallpaper>, Error>,
+) -> Result<Vec<Wallpaper>, Error> {
+    match &result {
+        Ok(_) => ctx.finish(),
+        Err(e) => ctx.fail(e.to_string()),
+    }
+
+    hooks::trigger(conn, Event::Scan, vec![hooks::scan_env(ctx.progress())]);
+
+    result
+}
@@ -0,0 +1,233 @@
+use crate::error::Error;
+use crate::utils::fs::{expand_home, get_config_dir};
+use serde::{Deserialize, Deserializer};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const FILE_NAME: &str = "config.toml";
+
+/// Printed by `config print-default`, parses to [`Config::default`].
+pub const DEFAULT_CONFIG: &str = r#"# hypr-nitrogen configuration, read from ~/.config/hypr-nitrogen/config.toml at startup.
+# Every key is optional, missing ones keep the values below.
+
+# off, error, warn, info, debug or trace, `--verbose` logs everything
+log_level = "info"
+
+[gui]
+# Sets GDK_BACKEND=wayland, the AppImage runs under XWayland otherwise
+# https://github.com/tauri-apps/tauri/issues/11790
+force_wayland = true
+
+[database]
+# ~/.local/share/hypr-nitrogen/data.db when unset
+# path = "~/.local/share/hypr-nitrogen/data.db"
+
+[thumbnails]
+# Thumbnails are cropped to fill this size, existing ones are kept until their wallpaper changes
+width = 400
+height = 200
+
+[scan]
+# Files picked up by scans and the source watcher, case insensitive
+extensions = ["jpg", "jpeg", "png", "gif", "webp"]
+
+[hyprpaper]
+# Socket file in $XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE
+socket = ".hyprpaper.sock"
+# Requests sent over the socket
+preload = "preload"
+wallpaper = "wallpaper"
+unload = "unload"
+listactive = "listactive"
+"#;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn filter(self) -> log::LevelFilter {
+        match self {
+            Self::Off => log::LevelFilter::Off,
+            Self::Error => log::LevelFilter::Error,
+            Self::Warn => log::LevelFilter::Warn,
+            Self::Info => log::LevelFilter::Info,
+            Self::Debug => log::LevelFilter::Debug,
+            Self::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub log_level: LogLevel,
+    pub gui: Gui,
+    pub database: Database,
+    pub thumbnails: Thumbnails,
+    pub scan: Scan,
+    pub hyprpaper: Hyprpaper,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Gui {
+    pub force_wayland: bool,
+}
+
+impl Default for Gui {
+    fn default() -> Self {
+        Self {
+            force_wayland: true,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Database {
+    /// `~/` is expanded, see [`Database::path`]
+    path: Option<String>,
+}
+
+impl Database {
+    pub fn path(&self) -> Option<PathBuf> {
+        self.path.as_deref().map(expand_home)
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thumbnails {
+    pub width: NonZeroU32,
+    pub height: NonZeroU32,
+}
+
+impl Default for Thumbnails {
+    fn default() -> Self {
+        Self {
+            width: NonZeroU32::new(400).unwrap(),
+            height: NonZeroU32::new(200).unwrap(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scan {
+    /// Lowercase, without the dot
+    #[serde(deserialize_with = "extensions")]
+    pub extensions: Vec<String>,
+}
+
+impl Default for Scan {
+    fn default() -> Self {
+        Self {
+            extensions: ["jpg", "jpeg", "png", "gif", "webp"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Hyprpaper {
+    #[serde(deserialize_with = "word")]
+    pub socket: String,
+    #[serde(deserialize_with = "word")]
+    pub preload: String,
+    #[serde(deserialize_with = "word")]
+    pub wallpaper: String,
+    #[serde(deserialize_with = "word")]
+    pub unload: String,
+    #[serde(deserialize_with = "word")]
+    pub listactive: String,
+}
+
+impl Default for Hyprpaper {
+    fn default() -> Self {
+        Self {
+            socket: String::from(".hyprpaper.sock"),
+            preload: String::from("preload"),
+            wallpaper: String::from("wallpaper"),
+            unload: String::from("unload"),
+            listactive: String::from("listactive"),
+        }
+    }
+}
+
+/// Non-empty and without whitespace, errors raised here point at the value's line.
+fn word<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        return Err(serde::de::Error::custom(
+            "expected a non-empty value without spaces",
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Accepts `.JPG` as well as `jpg`.
+fn extensions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let values = Vec::<String>::deserialize(deserializer)?;
+
+    if values.is_empty() {
+        return Err(serde::de::Error::custom("expected at least one extension"));
+    }
+
+    values
+        .into_iter()
+        .map(|v| {
+            let extension = v.trim().trim_start_matches('.').to_lowercase();
+
+            if extension.is_empty() || extension.contains(['.', '/']) {
+                Err(serde::de::Error::custom(format!("invalid extension '{v}'")))
+            } else {
+                Ok(extension)
+            }
+        })
+        .collect()
+}
+
+/// `~/.config/hypr-nitrogen/config.toml`
+pub fn path() -> PathBuf {
+    get_config_dir().join(FILE_NAME)
+}
+
+/// Defaults when `path` doesn't exist. Errors include the line and column of the bad value.
+pub fn load(path: &Path) -> Result<Config, Error> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    toml::from_str(&text)
+        .map_err(|e| Error::InvalidArgument(format!("{}: {e}", path.to_string_lossy())))
+}
+
+/// Makes `config` the one [`get`] returns, only the first call has an effect.
+pub fn init(config: Config) {
+    if CONFIG.set(config).is_err() {
+        log::warn!("Configuration already loaded");
+    }
+}
+
+/// Configuration loaded at startup, the defaults before that.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
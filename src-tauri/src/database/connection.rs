@@ -1,3 +1,4 @@
+use crate::config;
 use crate::utils::fs::get_app_data_dir;
 use diesel::{r2d2, RunQueryDsl, SqliteConnection};
 use std::path::PathBuf;
@@ -47,7 +48,9 @@ pub fn get_connection_pool() -> DbPool {
 }
 
 pub fn get_database_url() -> String {
-    if cfg!(dev) {
+    if let Some(path) = config::get().database.path() {
+        path.to_string_lossy().to_string()
+    } else if cfg!(dev) {
         "./dev.db".to_string()
     } else {
         get_database_location().to_str().unwrap().to_string()
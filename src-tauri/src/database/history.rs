@@ -0,0 +1,155 @@
+use crate::database::models::{History, NewHistory};
+use crate::schema;
+use crate::utils::now;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use std::fmt;
+
+/// Entries kept per screen, older ones are dropped when a new one is recorded.
+const HISTORY_LIMIT: i64 = 100;
+
+/// What caused a wallpaper change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Origin {
+    Manual,
+    Random,
+    Rotation,
+    Restore,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Manual => write!(f, "manual"),
+            Self::Random => write!(f, "random"),
+            Self::Rotation => write!(f, "rotation"),
+            Self::Restore => write!(f, "restore"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Back,
+    Forward,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Back => write!(f, "previous"),
+            Self::Forward => write!(f, "next"),
+        }
+    }
+}
+
+/// Appends an entry for `screen` and moves its back/forward position to it. A restore of
+/// the entry currently shown adds nothing, startup would fill the history with copies.
+pub fn record(
+    conn: &mut SqliteConnection,
+    screen: &str,
+    wallpaper_id: &str,
+    mode: &str,
+    origin: Origin,
+) -> QueryResult<()> {
+    if origin == Origin::Restore
+        && current(conn, screen)?.is_some_and(|c| c.wallpaper_id == wallpaper_id && c.mode == mode)
+    {
+        return Ok(());
+    }
+
+    let entry = NewHistory::new(
+        screen.to_string(),
+        wallpaper_id.to_string(),
+        mode.to_string(),
+        origin.to_string(),
+        now(),
+    );
+
+    diesel::insert_into(schema::history::table)
+        .values(&entry)
+        .execute(conn)?;
+
+    diesel::delete(schema::history_cursor::table.find(screen)).execute(conn)?;
+
+    let cutoff = schema::history::table
+        .filter(schema::history::screen.eq(screen))
+        .select(schema::history::id)
+        .order(schema::history::id.desc())
+        .offset(HISTORY_LIMIT)
+        .first::<i32>(conn)
+        .optional()?;
+
+    if let Some(id) = cutoff {
+        diesel::delete(
+            schema::history::table
+                .filter(schema::history::screen.eq(screen))
+                .filter(schema::history::id.le(id)),
+        )
+        .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Entry currently shown on `screen`, the newest one unless the user went back.
+fn current(conn: &mut SqliteConnection, screen: &str) -> QueryResult<Option<History>> {
+    let cursor = schema::history_cursor::table
+        .find(screen)
+        .select(schema::history_cursor::history_id)
+        .first::<i32>(conn)
+        .optional()?;
+
+    match cursor {
+        Some(id) => schema::history::table
+            .find(id)
+            .get_result::<History>(conn)
+            .optional(),
+        None => schema::history::table
+            .filter(schema::history::screen.eq(screen))
+            .order(schema::history::id.desc())
+            .first::<History>(conn)
+            .optional(),
+    }
+}
+
+/// Entry next to the current one in `direction`, skipping entries of the same wallpaper
+/// so a restore doesn't count as a step.
+pub fn step(
+    conn: &mut SqliteConnection,
+    screen: &str,
+    direction: Direction,
+) -> QueryResult<Option<History>> {
+    let Some(current) = current(conn, screen)? else {
+        return Ok(None);
+    };
+
+    let query = schema::history::table
+        .filter(schema::history::screen.eq(screen))
+        .filter(schema::history::wallpaper_id.ne(&current.wallpaper_id))
+        .into_boxed();
+
+    let query = match direction {
+        Direction::Back => query
+            .filter(schema::history::id.lt(current.id))
+            .order(schema::history::id.desc()),
+        Direction::Forward => query
+            .filter(schema::history::id.gt(current.id))
+            .order(schema::history::id.asc()),
+    };
+
+    query.first::<History>(conn).optional()
+}
+
+pub fn set_cursor(conn: &mut SqliteConnection, screen: &str, history_id: i32) -> QueryResult<()> {
+    diesel::insert_into(schema::history_cursor::table)
+        .values((
+            schema::history_cursor::screen.eq(screen),
+            schema::history_cursor::history_id.eq(history_id),
+        ))
+        .on_conflict(schema::history_cursor::screen)
+        .do_update()
+        .set(schema::history_cursor::history_id.eq(excluded(schema::history_cursor::history_id)))
+        .execute(conn)
+        .map(|_| ())
+}
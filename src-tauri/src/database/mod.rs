@@ -1,2 +1,6 @@
 pub mod connection;
+pub mod history;
 pub mod models;
+pub mod search;
+pub mod settings;
+pub mod tags;
@@ -44,6 +44,112 @@ pub struct Wallpaper {
     pub resolution: Option<String>,
     pub wallpaper_source_id: String,
     pub keywords: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub aspect_ratio: Option<f64>,
+    pub file_size: Option<i64>,
+    pub mtime: Option<i64>,
+    /// Internal, compared by `cmd_get_duplicates`
+    #[ts(skip)]
+    #[serde(skip)]
+    pub phash: Option<i64>,
+    /// Comma separated `#rrggbb` colors, most dominant first
+    pub palette: Option<String>,
+    /// From the metadata file of the source
+    pub caption: Option<String>,
+    /// From the metadata file of the source
+    pub category: Option<String>,
+}
+
+/// A file with the same content as `wallpaper_id`, which has the only row for it.
+#[derive(TS, Queryable, Identifiable, Associations, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(table_name = schema::duplicates)]
+#[diesel(primary_key(path))]
+#[diesel(belongs_to(Wallpaper, foreign_key = wallpaper_id))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Duplicate {
+    pub path: String,
+    pub wallpaper_id: String,
+    pub wallpaper_source_id: String,
+    pub file_size: Option<i64>,
+    pub mtime: Option<i64>,
+}
+
+/// Wallpapers that look alike, best resolution first, and the exact copies of each.
+#[derive(TS, Debug, Serialize, Clone)]
+#[ts(export)]
+pub struct DuplicateCluster {
+    pub wallpapers: Vec<Wallpaper>,
+    pub copies: Vec<Duplicate>,
+}
+
+/// Result of `cmd_search_wallpapers`, `next_cursor` is `None` on the last page.
+#[derive(TS, Debug, Serialize, Clone)]
+#[ts(export)]
+pub struct WallpaperPage {
+    pub wallpapers: Vec<Wallpaper>,
+    /// Matches of the search across all pages
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+#[derive(TS, Queryable, Identifiable, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(table_name = schema::rotations)]
+#[diesel(primary_key(screen))]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Rotation {
+    pub screen: String,
+    pub interval_secs: i32,
+    pub wallpaper_source_id: Option<String>,
+    pub favorites_only: bool,
+    pub shuffle: bool,
+    pub mode: String,
+    pub enabled: bool,
+    pub last_changed_at: i64,
+    pub collection_id: Option<String>,
+}
+
+#[derive(TS, Queryable, Identifiable, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(table_name = schema::collections)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+/// A tag and the number of wallpapers that have it.
+#[derive(TS, Queryable, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
+/// A tag of one wallpaper, `origin` is `filename`, `metadata` or `user`.
+#[derive(TS, Queryable, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct WallpaperTag {
+    pub name: String,
+    pub origin: String,
+}
+
+#[derive(TS, Queryable, Identifiable, Debug, Serialize, Clone)]
+#[ts(export)]
+#[diesel(table_name = schema::history)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct History {
+    pub id: i32,
+    pub screen: String,
+    pub wallpaper_id: String,
+    pub mode: String,
+    pub origin: String,
+    pub created_at: i64,
 }
 
 #[derive(Insertable, Debug)]
@@ -93,6 +199,15 @@ pub struct NewWallpaper {
     pub resolution: Option<String>,
     pub wallpaper_source_id: String,
     pub keywords: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub aspect_ratio: Option<f64>,
+    pub file_size: Option<i64>,
+    pub mtime: Option<i64>,
+    pub phash: Option<i64>,
+    pub palette: Option<String>,
+    pub caption: Option<String>,
+    pub category: Option<String>,
 }
 
 impl NewWallpaper {
@@ -113,6 +228,129 @@ impl NewWallpaper {
             resolution,
             wallpaper_source_id,
             keywords,
+            width: None,
+            height: None,
+            aspect_ratio: None,
+            file_size: None,
+            mtime: None,
+            phash: None,
+            palette: None,
+            caption: None,
+            category: None,
+        }
+    }
+}
+
+#[derive(Insertable, AsChangeset, Debug)]
+#[diesel(table_name = schema::rotations)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewRotation {
+    pub screen: String,
+    pub interval_secs: i32,
+    pub wallpaper_source_id: Option<String>,
+    pub favorites_only: bool,
+    pub shuffle: bool,
+    pub mode: String,
+    pub enabled: bool,
+    pub collection_id: Option<String>,
+}
+
+impl NewRotation {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        screen: String,
+        interval_secs: i32,
+        wallpaper_source_id: Option<String>,
+        collection_id: Option<String>,
+        favorites_only: bool,
+        shuffle: bool,
+        mode: String,
+        enabled: bool,
+    ) -> Self {
+        Self {
+            screen,
+            interval_secs,
+            wallpaper_source_id,
+            favorites_only,
+            shuffle,
+            mode,
+            enabled,
+            collection_id,
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = schema::collections)]
+pub struct NewCollection {
+    pub id: String,
+    pub name: String,
+    pub created_at: i64,
+}
+
+impl NewCollection {
+    pub fn new(name: String, created_at: i64) -> Self {
+        Self {
+            id: nanoid!(),
+            name,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::history)]
+pub struct NewHistory {
+    pub screen: String,
+    pub wallpaper_id: String,
+    pub mode: String,
+    pub origin: String,
+    pub created_at: i64,
+}
+
+impl NewHistory {
+    pub fn new(
+        screen: String,
+        wallpaper_id: String,
+        mode: String,
+        origin: String,
+        created_at: i64,
+    ) -> Self {
+        Self {
+            screen,
+            wallpaper_id,
+            mode,
+            origin,
+            created_at,
+        }
+    }
+}
+
+#[derive(Insertable, AsChangeset, Debug)]
+#[diesel(table_name = schema::duplicates)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewDuplicate {
+    pub path: String,
+    pub wallpaper_id: String,
+    pub wallpaper_source_id: String,
+    pub file_size: Option<i64>,
+    pub mtime: Option<i64>,
+}
+
+impl NewDuplicate {
+    pub fn new(
+        path: String,
+        wallpaper_id: String,
+        wallpaper_source_id: String,
+        file_size: Option<i64>,
+        mtime: Option<i64>,
+    ) -> Self {
+        Self {
+            path,
+            wallpaper_id,
+            wallpaper_source_id,
+            file_size,
+            mtime,
         }
     }
 }
@@ -0,0 +1,291 @@
+use crate::database::models::Wallpaper;
+use crate::database::tags::Condition;
+use crate::error::Error;
+use crate::ipc::SearchSort;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Text};
+use serde::{Deserialize, Serialize};
+
+/// A sort key of the last wallpaper of a page.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+enum Value {
+    Integer(i64),
+    Text(String),
+}
+
+enum Getter {
+    Integer(fn(&Wallpaper) -> i64),
+    Text(fn(&Wallpaper) -> String),
+}
+
+struct Key {
+    /// Never NULL, rows with a NULL key couldn't be compared to a cursor
+    sql: &'static str,
+    descending: bool,
+    /// Same value as `sql`, read from a row
+    getter: Getter,
+}
+
+impl Key {
+    fn value(&self, wallpaper: &Wallpaper) -> Value {
+        match self.getter {
+            Getter::Integer(f) => Value::Integer(f(wallpaper)),
+            Getter::Text(f) => Value::Text(f(wallpaper)),
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (&self.getter, value),
+            (Getter::Integer(_), Value::Integer(_)) | (Getter::Text(_), Value::Text(_))
+        )
+    }
+
+    /// `sql > value`, or `<` when descending.
+    fn after<QS: 'static>(&self, value: &Value) -> Condition<QS> {
+        let op = if self.descending { "<" } else { ">" };
+        let condition = sql::<Bool>(&format!("{} {op} ", self.sql));
+
+        match value {
+            Value::Integer(v) => Box::new(condition.bind::<BigInt, _>(*v)),
+            Value::Text(v) => Box::new(condition.bind::<Text, _>(v.clone())),
+        }
+    }
+
+    fn equals<QS: 'static>(&self, value: &Value) -> Condition<QS> {
+        let condition = sql::<Bool>(&format!("{} = ", self.sql));
+
+        match value {
+            Value::Integer(v) => Box::new(condition.bind::<BigInt, _>(*v)),
+            Value::Text(v) => Box::new(condition.bind::<Text, _>(v.clone())),
+        }
+    }
+}
+
+/// Order of a search, the id comes last so that every row has its own place and a page
+/// can start right after the last row of the previous one.
+pub struct Order(Vec<Key>);
+
+impl Order {
+    pub fn new(sort: SearchSort, descending: bool, favorites_first: bool) -> Self {
+        let mut keys = Vec::new();
+
+        if favorites_first {
+            keys.push(Key {
+                sql: "wallpapers.is_favorite",
+                descending: true,
+                getter: Getter::Integer(|w| w.is_favorite as i64),
+            });
+        }
+
+        keys.push(match sort {
+            SearchSort::Path => Key {
+                sql: "wallpapers.path",
+                descending,
+                getter: Getter::Text(|w| w.path.clone()),
+            },
+            SearchSort::Modified => Key {
+                sql: "coalesce(wallpapers.mtime, 0)",
+                descending,
+                getter: Getter::Integer(|w| w.mtime.unwrap_or(0)),
+            },
+            SearchSort::Size => Key {
+                sql: "coalesce(wallpapers.file_size, 0)",
+                descending,
+                getter: Getter::Integer(|w| w.file_size.unwrap_or(0)),
+            },
+            SearchSort::Resolution => Key {
+                sql: "coalesce(wallpapers.width * wallpapers.height, 0)",
+                descending,
+                getter: Getter::Integer(|w| {
+                    w.width
+                        .zip(w.height)
+                        .map_or(0, |(w, h)| w as i64 * h as i64)
+                }),
+            },
+        });
+
+        keys.push(Key {
+            sql: "wallpapers.id",
+            descending,
+            getter: Getter::Text(|w| w.id.clone()),
+        });
+
+        Self(keys)
+    }
+
+    /// `ORDER BY` clause.
+    pub fn sql(&self) -> String {
+        self.0
+            .iter()
+            .map(|k| format!("{} {}", k.sql, if k.descending { "DESC" } else { "ASC" }))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Opaque cursor pointing right after `wallpaper`, hex encoded JSON of its sort keys.
+    pub fn cursor(&self, wallpaper: &Wallpaper) -> String {
+        let values: Vec<Value> = self.0.iter().map(|k| k.value(wallpaper)).collect();
+
+        serde_json::to_string(&values)
+            .unwrap_or_default()
+            .bytes()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    fn decode(&self, cursor: &str) -> Option<Vec<Value>> {
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        let values: Vec<Value> = serde_json::from_slice(&bytes).ok()?;
+
+        let matches =
+            values.len() == self.0.len() && self.0.iter().zip(&values).all(|(k, v)| k.accepts(v));
+
+        matches.then_some(values)
+    }
+
+    /// Rows after the one `cursor` points at. Fails for cursors of another sort order.
+    pub fn after<QS: 'static>(&self, cursor: &str) -> Result<Condition<QS>, Error> {
+        let Some(values) = self.decode(cursor) else {
+            return Err(Error::InvalidArgument(format!(
+                "Invalid cursor '{cursor}' for this sort order"
+            )));
+        };
+
+        // (a > x) OR (a = x AND ((b > y) OR (b = y AND ...)))
+        let mut pairs = self.0.iter().zip(&values).rev();
+        let (key, value) = pairs.next().expect("the id is always a key");
+        let mut condition = key.after(value);
+
+        for (key, value) in pairs {
+            condition = Box::new(key.after(value).or(key.equals(value).and(condition)));
+        }
+
+        Ok(condition)
+    }
+}
+
+/// FTS5 query matching every word of `text` as a prefix, `None` when it has no words.
+pub fn text_query(text: &str) -> Option<String> {
+    let words: Vec<String> = text
+        .split_whitespace()
+        // The tokenizer drops punctuation, a phrase of it alone would match nothing
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .map(|w| format!("\"{}\"*", w.replace('"', "\"\"")))
+        .collect();
+
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Wallpapers whose keywords, file name, caption or category match an FTS5 query from [`text_query`].
+pub fn text_condition<QS: 'static>(query: String) -> Condition<QS> {
+    Box::new(
+        sql::<Bool>(
+            "wallpapers.id IN (SELECT wallpaper_id FROM wallpapers_fts WHERE wallpapers_fts MATCH ",
+        )
+        .bind::<Text, _>(query)
+        .sql(")"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema;
+
+    fn wallpaper(id: &str, path: &str) -> Wallpaper {
+        Wallpaper {
+            id: id.to_string(),
+            is_favorite: true,
+            signature: String::new(),
+            path: path.to_string(),
+            thumbnail_path: String::new(),
+            resolution: None,
+            wallpaper_source_id: String::new(),
+            keywords: None,
+            width: Some(1920),
+            height: Some(1080),
+            aspect_ratio: None,
+            file_size: None,
+            mtime: None,
+            phash: None,
+            palette: None,
+            caption: None,
+            category: None,
+        }
+    }
+
+    fn after_sql(order: &Order, cursor: &str) -> Result<String, Error> {
+        let condition = order.after::<schema::wallpapers::table>(cursor)?;
+
+        Ok(diesel::debug_query::<diesel::sqlite::Sqlite, _>(
+            &schema::wallpapers::table.filter(condition),
+        )
+        .to_string())
+    }
+
+    #[test]
+    fn text_query_prefixes_words() {
+        assert_eq!(
+            text_query("  snowy Forest ").as_deref(),
+            Some("\"snowy\"* \"Forest\"*")
+        );
+        assert_eq!(
+            text_query("say \"hi\"").as_deref(),
+            Some("\"say\"* \"\"\"hi\"\"\"*")
+        );
+        assert_eq!(text_query("a - b").as_deref(), Some("\"a\"* \"b\"*"));
+        assert_eq!(text_query(" -- ... "), None);
+        assert_eq!(text_query(""), None);
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let order = Order::new(SearchSort::Resolution, true, true);
+        let cursor = order.cursor(&wallpaper("abc", "/a.png"));
+
+        assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(
+            order.decode(&cursor),
+            Some(vec![
+                Value::Integer(1),
+                Value::Integer(1920 * 1080),
+                Value::Text(String::from("abc")),
+            ])
+        );
+
+        let sql = after_sql(&order, &cursor).unwrap();
+        assert!(sql.contains("wallpapers.is_favorite < ? OR (wallpapers.is_favorite = ? AND"));
+        assert!(sql.ends_with("-- binds: [1, 1, 2073600, 2073600, \"abc\"]"));
+    }
+
+    #[test]
+    fn cursor_of_another_order() {
+        let by_path = Order::new(SearchSort::Path, false, false);
+        let by_size = Order::new(SearchSort::Size, false, false);
+        let cursor = by_path.cursor(&wallpaper("abc", "/a.png"));
+
+        assert!(after_sql(&by_path, &cursor).is_ok());
+        // Same number of keys, a text where the size should be
+        assert!(after_sql(&by_size, &cursor).is_err());
+        // One key more
+        assert!(after_sql(&Order::new(SearchSort::Path, false, true), &cursor).is_err());
+    }
+
+    #[test]
+    fn malformed_cursors() {
+        let order = Order::new(SearchSort::Path, false, false);
+
+        for cursor in ["", "5", "zz", "5b5d", "7b7d", "é1"] {
+            assert!(
+                matches!(after_sql(&order, cursor), Err(Error::InvalidArgument(_))),
+                "{cursor}"
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::schema;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+
+pub const BACKEND: &str = "backend";
+/// Wallpaper id applied to newly connected screens that have no `active` row.
+pub const FALLBACK_WALLPAPER: &str = "fallback_wallpaper";
+pub const FALLBACK_MODE: &str = "fallback_mode";
+/// `"true"` keeps sources in sync with a file system watcher.
+pub const WATCH_SOURCES: &str = "watch_sources";
+/// `"true"` writes theme files from the palette of each applied wallpaper.
+pub const THEME_EXPORT: &str = "theme.export";
+/// Where theme files are written, defaults to `theme` in the cache dir.
+pub const THEME_DIR: &str = "theme.dir";
+/// Shell command run after theme files are written.
+pub const THEME_RELOAD_COMMAND: &str = "theme.reload_command";
+/// Shell commands run after each event, see `utils::hooks`.
+pub const HOOK_WALLPAPER_SET: &str = "hooks.wallpaper_set";
+pub const HOOK_RESTORE: &str = "hooks.restore";
+pub const HOOK_SCAN: &str = "hooks.scan";
+pub const HOOK_ROTATION: &str = "hooks.rotation";
+/// Seconds a hook may run before it's killed.
+pub const HOOK_TIMEOUT: &str = "hooks.timeout";
+
+/// Keys accepted by `cmd_set_setting`, backend specific keys live with their backend.
+pub const KNOWN_SETTINGS: &[&str] = &[
+    BACKEND,
+    FALLBACK_WALLPAPER,
+    FALLBACK_MODE,
+    WATCH_SOURCES,
+    THEME_EXPORT,
+    THEME_DIR,
+    THEME_RELOAD_COMMAND,
+    HOOK_WALLPAPER_SET,
+    HOOK_RESTORE,
+    HOOK_SCAN,
+    HOOK_ROTATION,
+    HOOK_TIMEOUT,
+];
+
+pub fn get_setting(conn: &mut SqliteConnection, key: &str) -> QueryResult<Option<String>> {
+    schema::settings::table
+        .find(key)
+        .select(schema::settings::value)
+        .get_result::<String>(conn)
+        .optional()
+}
+
+pub fn set_setting(conn: &mut SqliteConnection, key: &str, value: &str) -> QueryResult<()> {
+    diesel::insert_into(schema::settings::table)
+        .values((
+            schema::settings::key.eq(key),
+            schema::settings::value.eq(value),
+        ))
+        .on_conflict(schema::settings::key)
+        .do_update()
+        .set(schema::settings::value.eq(excluded(schema::settings::value)))
+        .execute(conn)
+        .map(|_| ())
+}
+
+pub fn get_settings(conn: &mut SqliteConnection) -> QueryResult<Vec<(String, String)>> {
+    schema::settings::table
+        .select((schema::settings::key, schema::settings::value))
+        .get_results::<(String, String)>(conn)
+}
@@ -0,0 +1,370 @@
+use crate::error::Error;
+use crate::schema;
+use diesel::dsl::sql;
+use diesel::expression::is_aggregate;
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Text};
+use diesel::sqlite::Sqlite;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::FromStr;
+
+/// Where a tag of a wallpaper came from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Origin {
+    /// Words of the file name, when the metadata file has no entry for the file
+    Filename,
+    /// `tags` of the file's entry in the metadata file, see [`crate::utils::metadata`]
+    Metadata,
+    User,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filename => write!(f, "filename"),
+            Self::Metadata => write!(f, "metadata"),
+            Self::User => write!(f, "user"),
+        }
+    }
+}
+
+/// Characters that can't be part of a tag, they separate tags in keywords.
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | '(' | ')')
+}
+
+/// Lowercases a tag given by the user.
+pub fn normalize(name: &str) -> Result<String, Error> {
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(Error::InvalidArgument(String::from(
+            "Tag name can't be empty",
+        )));
+    }
+
+    if name.contains(is_separator) {
+        return Err(Error::InvalidArgument(format!(
+            "Tag '{name}' can't contain whitespace, commas or parentheses"
+        )));
+    }
+
+    Ok(name.to_lowercase())
+}
+
+/// Splits keywords built from a file name or a metadata file into tags, in order and
+/// without repeats.
+pub fn from_keywords(keywords: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for word in keywords.split(is_separator).filter(|w| !w.is_empty()) {
+        let tag = word.to_lowercase();
+
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    tags
+}
+
+/// Gives `wallpaper_id` the tags, creating the missing ones. Tags it already has keep their
+/// origin.
+pub fn attach(
+    conn: &mut SqliteConnection,
+    wallpaper_id: &str,
+    names: &[String],
+    origin: Origin,
+) -> QueryResult<()> {
+    for name in names {
+        diesel::insert_or_ignore_into(schema::tags::table)
+            .values(schema::tags::name.eq(name))
+            .execute(conn)?;
+    }
+
+    let tag_ids = schema::tags::table
+        .filter(schema::tags::name.eq_any(names))
+        .select(schema::tags::id)
+        .get_results::<i32>(conn)?;
+
+    for tag_id in tag_ids {
+        diesel::insert_or_ignore_into(schema::wallpaper_tags::table)
+            .values((
+                schema::wallpaper_tags::wallpaper_id.eq(wallpaper_id),
+                schema::wallpaper_tags::tag_id.eq(tag_id),
+                schema::wallpaper_tags::origin.eq(origin.to_string()),
+            ))
+            .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Takes the tags off `wallpaper_id`, whatever their origin.
+pub fn detach(
+    conn: &mut SqliteConnection,
+    wallpaper_id: &str,
+    names: &[String],
+) -> QueryResult<usize> {
+    diesel::delete(
+        schema::wallpaper_tags::table
+            .filter(schema::wallpaper_tags::wallpaper_id.eq(wallpaper_id))
+            .filter(
+                schema::wallpaper_tags::tag_id.eq_any(
+                    schema::tags::table
+                        .filter(schema::tags::name.eq_any(names))
+                        .select(schema::tags::id),
+                ),
+            ),
+    )
+    .execute(conn)
+}
+
+/// Deletes tags no wallpaper has anymore.
+pub fn remove_unused(conn: &mut SqliteConnection) -> QueryResult<usize> {
+    diesel::delete(
+        schema::tags::table
+            .filter(diesel::dsl::not(schema::tags::id.eq_any(
+                schema::wallpaper_tags::table.select(schema::wallpaper_tags::tag_id),
+            ))),
+    )
+    .execute(conn)
+}
+
+/// Rewrites the keywords of `wallpaper_id` from its tags, so that searching keywords finds
+/// tags added by the user. Tags keep the order they were given in, the file name's words
+/// stay in place and tags added later come after them.
+pub fn sync_keywords(conn: &mut SqliteConnection, wallpaper_id: &str) -> QueryResult<()> {
+    let names = schema::tags::table
+        .inner_join(schema::wallpaper_tags::table)
+        .filter(schema::wallpaper_tags::wallpaper_id.eq(wallpaper_id))
+        // Insertion order, `attach` adds the rows one by one
+        .order(sql::<BigInt>("wallpaper_tags.rowid").asc())
+        .select(schema::tags::name)
+        .get_results::<String>(conn)?;
+
+    diesel::update(schema::wallpapers::table.find(wallpaper_id))
+        .set(schema::wallpapers::keywords.eq(names.join(" ")))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// SQL condition on `wallpapers` built from a [`Query`], usable in any query that selects
+/// from that table.
+pub type Condition<QS> =
+    Box<dyn BoxableExpression<QS, Sqlite, (), is_aggregate::Never, SqlType = Bool>>;
+
+/// Boolean tag query such as `nature AND NOT (snow OR winter)`. Operators are uppercase,
+/// `NOT` binds tighter than `AND` which binds tighter than `OR`, and terms without an
+/// operator between them are joined with `AND`.
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    Tag(String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Tag(String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "("),
+            Self::Close => write!(f, ")"),
+            Self::And => write!(f, "AND"),
+            Self::Or => write!(f, "OR"),
+            Self::Not => write!(f, "NOT"),
+            Self::Tag(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+type Tokens = Peekable<std::vec::IntoIter<Token>>;
+
+/// Ends the word at hand, operators are only recognized in uppercase.
+fn push_word(word: &mut String, tokens: &mut Vec<Token>) -> Result<(), String> {
+    if word.is_empty() {
+        return Ok(());
+    }
+
+    tokens.push(match word.as_str() {
+        "AND" => Token::And,
+        "OR" => Token::Or,
+        "NOT" => Token::Not,
+        _ => Token::Tag(normalize(word).map_err(|e| e.to_string())?),
+    });
+
+    word.clear();
+    Ok(())
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+
+    for c in text.chars() {
+        match c {
+            '(' | ')' => {
+                push_word(&mut word, &mut tokens)?;
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => push_word(&mut word, &mut tokens)?,
+            c => word.push(c),
+        }
+    }
+
+    push_word(&mut word, &mut tokens)?;
+
+    Ok(tokens)
+}
+
+fn parse_or(tokens: &mut Tokens) -> Result<Query, String> {
+    let mut query = parse_and(tokens)?;
+
+    while tokens.next_if_eq(&Token::Or).is_some() {
+        query = Query::Or(Box::new(query), Box::new(parse_and(tokens)?));
+    }
+
+    Ok(query)
+}
+
+fn parse_and(tokens: &mut Tokens) -> Result<Query, String> {
+    let mut query = parse_not(tokens)?;
+
+    loop {
+        let implicit = matches!(
+            tokens.peek(),
+            Some(Token::Tag(_) | Token::Not | Token::Open)
+        );
+
+        if tokens.next_if_eq(&Token::And).is_none() && !implicit {
+            return Ok(query);
+        }
+
+        query = Query::And(Box::new(query), Box::new(parse_not(tokens)?));
+    }
+}
+
+fn parse_not(tokens: &mut Tokens) -> Result<Query, String> {
+    match tokens.next() {
+        Some(Token::Not) => Ok(Query::Not(Box::new(parse_not(tokens)?))),
+        Some(Token::Open) => {
+            let query = parse_or(tokens)?;
+
+            match tokens.next() {
+                Some(Token::Close) => Ok(query),
+                Some(token) => Err(format!("expected ')' instead of '{token}'")),
+                None => Err(String::from("missing ')'")),
+            }
+        }
+        Some(Token::Tag(name)) => Ok(Query::Tag(name)),
+        Some(token) => Err(format!("expected a tag instead of '{token}'")),
+        None => Err(String::from("expected a tag at the end")),
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let mut tokens = tokenize(text)?.into_iter().peekable();
+            let query = parse_or(&mut tokens)?;
+
+            match tokens.next() {
+                Some(token) => Err(format!("unexpected '{token}'")),
+                None => Ok(query),
+            }
+        };
+
+        parse().map_err(|e| Error::InvalidArgument(format!("Invalid tag query '{text}': {e}")))
+    }
+}
+
+impl Query {
+    pub fn condition<QS: 'static>(&self) -> Condition<QS> {
+        match self {
+            Self::Tag(name) => Box::new(
+                sql::<Bool>(
+                    "EXISTS (SELECT 1 FROM wallpaper_tags \
+                     INNER JOIN tags ON tags.id = wallpaper_tags.tag_id \
+                     WHERE wallpaper_tags.wallpaper_id = wallpapers.id AND tags.name = ",
+                )
+                .bind::<Text, _>(name.clone())
+                .sql(")"),
+            ),
+            Self::Not(query) => Box::new(diesel::dsl::not(query.condition::<QS>())),
+            Self::And(left, right) => Box::new(left.condition::<QS>().and(right.condition())),
+            Self::Or(left, right) => Box::new(left.condition::<QS>().or(right.condition())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str) -> Box<Query> {
+        Box::new(Query::Tag(name.to_string()))
+    }
+
+    #[test]
+    fn normalize_lowercases() {
+        assert_eq!(normalize("  Sunset ").unwrap(), "sunset");
+        assert_eq!(normalize("Ö-sea_2").unwrap(), "ö-sea_2");
+    }
+
+    #[test]
+    fn normalize_rejects_separators() {
+        for name in ["", "   ", "two words", "a,b", "(a)", "a\tb"] {
+            assert!(
+                matches!(normalize(name), Err(Error::InvalidArgument(_))),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn keywords_in_order_without_repeats() {
+        assert_eq!(
+            from_keywords("Snow forest, snow  (Night),forest"),
+            ["snow", "forest", "night"]
+        );
+        assert!(from_keywords(" ,() ").is_empty());
+    }
+
+    #[test]
+    fn query_precedence() {
+        let query: Query = "a OR NOT b c".parse().unwrap();
+
+        assert_eq!(
+            query,
+            Query::Or(
+                tag("a"),
+                Box::new(Query::And(Box::new(Query::Not(tag("b"))), tag("c")))
+            )
+        );
+        assert_eq!(
+            "(A OR b) AND c".parse::<Query>().unwrap(),
+            Query::And(Box::new(Query::Or(tag("a"), tag("b"))), tag("c"))
+        );
+    }
+
+    #[test]
+    fn query_errors() {
+        for text in ["", "a AND", "(a", "a)", "OR a", "a,b"] {
+            assert!(text.parse::<Query>().is_err(), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::backend;
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    Database(diesel::result::Error),
+    Pool(diesel::r2d2::PoolError),
+    NotFound(String),
+    InvalidArgument(String),
+    Backend(backend::Error),
+    Io(std::io::Error),
+    MissingDependency(String),
+    /// Per-screen failures of a batch, formatted as `screen: error`
+    ScreensFailed(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Database(e) => write!(f, "{e}"),
+            Self::Pool(e) => write!(f, "{e}"),
+            Self::NotFound(e) => write!(f, "{e}"),
+            Self::InvalidArgument(e) => write!(f, "{e}"),
+            Self::Backend(e) => write!(f, "{e}"),
+            Self::Io(e) => write!(f, "Os error: {e}"),
+            Self::MissingDependency(e) => write!(f, "{e}"),
+            Self::ScreensFailed(e) => write!(f, "{}", e.join(", ")),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(e: diesel::result::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+impl From<diesel::r2d2::PoolError> for Error {
+    fn from(e: diesel::r2d2::PoolError) -> Self {
+        Self::Pool(e)
+    }
+}
+
+impl From<backend::Error> for Error {
+    fn from(e: backend::Error) -> Self {
+        match e {
+            backend::Error::MissingCommand(_) => Self::MissingDependency(e.to_string()),
+            backend::Error::UnknownMode(_) => Self::InvalidArgument(e.to_string()),
+            _ => Self::Backend(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[derive(Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Error {
+    /// Stable identifier for scripts, never change an existing one.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Database(diesel::result::Error::NotFound) => "not_found",
+            Self::Database(_) | Self::Pool(_) => "database",
+            Self::NotFound(_) => "not_found",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::Backend(_) | Self::ScreensFailed(_) => "backend",
+            Self::Io(_) => "io",
+            Self::MissingDependency(_) => "missing_dependency",
+        }
+    }
+
+    /// Process exit code, `2` matches what clap uses for usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self.code() {
+            "invalid_argument" => 2,
+            "database" => 3,
+            "not_found" => 4,
+            "backend" => 5,
+            "io" => 6,
+            "missing_dependency" => 7,
+            _ => 1,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code(),
+            message: self.to_string(),
+        }
+    }
+}
@@ -1,47 +1,59 @@
 use serde::Deserialize;
-use std::process;
 
-use super::{DispatchErrorKind, Error, HYPRCTL_CMD, UNKNOWN_REQUEST_ERROR};
+use super::socket::{request, Socket};
+use super::{DispatchErrorKind, Error, UNKNOWN_REQUEST_ERROR};
 
-#[derive(Deserialize, Debug)]
-struct Monitor {
-    name: String,
+/// The part of a `j/monitors` entry we use. `width` and `height` are the mode in pixels,
+/// before scale and transform are applied, `x` and `y` the position in the layout.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Monitor {
+    pub name: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale: f64,
+    /// wl_output transform, odd values rotate by 90 or 270 degrees
+    pub transform: u8,
 }
 
-pub fn active_screens() -> Result<Vec<String>, Error> {
-    let mut screens: Vec<String> = Vec::new();
-
-    match process::Command::new(HYPRCTL_CMD)
-        .args(["monitors", "-j"])
-        .output()
-    {
-        Ok(output) => {
-            if let Ok(text) = String::from_utf8(output.stdout.clone()) {
-                if text == UNKNOWN_REQUEST_ERROR {
-                    log::error!("Failed to get active screens: unknown request");
-                    return Err(Error::Dispatch(DispatchErrorKind::UnknownRequest));
-                }
-            }
-
-            if output.status.success() {
-                match serde_json::from_slice::<Vec<Monitor>>(&output.stdout) {
-                    Ok(json) => {
-                        for item in json {
-                            screens.push(item.name);
-                        }
-                    }
-                    Err(_) => {
-                        log::error!("Failed to get active screens, json parsing failed");
-                        return Err(Error::JsonParsing);
-                    }
-                }
-            }
+impl Monitor {
+    /// Size of the wallpaper surface in pixels, rotated monitors swap width and height.
+    pub fn pixel_size(&self) -> (u32, u32) {
+        if self.transform % 2 == 1 {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
         }
-        Err(e) => {
-            log::error!("Failed to get active screens: {e}");
-            return Err(Error::Os(e));
+    }
+
+    /// Size in layout coordinates, which `x` and `y` are given in.
+    pub fn logical_size(&self) -> (f64, f64) {
+        let (width, height) = self.pixel_size();
+        (
+            f64::from(width) / self.scale,
+            f64::from(height) / self.scale,
+        )
+    }
+}
+
+pub fn monitors() -> Result<Vec<Monitor>, Error> {
+    let reply = request(Socket::Hyprland, "j/monitors")?;
+
+    if reply.trim_end() == UNKNOWN_REQUEST_ERROR {
+        log::error!("Failed to get monitors: unknown request");
+        return Err(Error::Dispatch(DispatchErrorKind::UnknownRequest));
+    }
+
+    match serde_json::from_str::<Vec<Monitor>>(&reply) {
+        Ok(json) => Ok(json),
+        Err(_) => {
+            log::error!("Failed to get monitors, json parsing failed");
+            Err(Error::JsonParsing)
         }
     }
+}
 
-    Ok(screens)
+pub fn active_screens() -> Result<Vec<String>, Error> {
+    Ok(monitors()?.into_iter().map(|item| item.name).collect())
 }
@@ -0,0 +1,49 @@
+use super::socket::Socket;
+use super::{DispatchErrorKind, Error};
+use std::io::{BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+
+/// The subset of Hyprland's `.socket2.sock` events we react to.
+#[derive(Debug)]
+pub enum Event {
+    MonitorAdded(String),
+    MonitorRemoved(String),
+}
+
+impl Event {
+    /// Parses a `name>>data` line, events we don't care about return `None`.
+    fn parse(line: &str) -> Option<Self> {
+        let (name, data) = line.split_once(">>")?;
+
+        match name {
+            "monitoradded" => Some(Self::MonitorAdded(data.to_string())),
+            "monitorremoved" => Some(Self::MonitorRemoved(data.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Blocks on the event socket and calls `handler` for every [`Event`].
+/// Returns once Hyprland closes the connection.
+pub fn listen<F: FnMut(Event)>(mut handler: F) -> Result<(), Error> {
+    let path = Socket::HyprlandEvents.path()?;
+
+    let stream = match UnixStream::connect(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Failed to connect to '{}': {e}", path.to_string_lossy());
+            return Err(Error::Dispatch(DispatchErrorKind::SockConnectionFailed));
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = line.map_err(Error::Os)?;
+
+        if let Some(event) = Event::parse(&line) {
+            log::debug!("Hyprland event: {event:?}");
+            handler(event);
+        }
+    }
+
+    Ok(())
+}
@@ -1,24 +1,25 @@
 // TODO: Better Error handling
 
 mod active_screens;
+mod events;
 mod preload;
 mod set_wallpaper;
+mod socket;
 mod unload;
 
-pub use active_screens::active_screens;
-pub use set_wallpaper::set_wallpaper;
+pub use active_screens::{active_screens, monitors, Monitor};
+pub use events::{listen, Event};
+pub use preload::preload;
+pub use set_wallpaper::{set_wallpaper, set_wallpapers};
 pub use unload::unload;
 
 use std::fmt;
 
-pub const HYPRCTL_CMD: &str = "hyprctl";
-pub const HYPRPAPER_CMD: &str = "hyprpaper";
-
-pub const UNKNOWN_REQUEST_ERROR: &str = "unknown request\n";
+pub const UNKNOWN_REQUEST_ERROR: &str = "unknown request";
 pub const NO_SUCH_FILE_ERROR: &str = "no such file:";
-pub const WALLPAPER_NOT_PRELOADED: &str = "wallpaper failed (not preloaded)\n";
+pub const WALLPAPER_NOT_PRELOADED: &str = "wallpaper failed (not preloaded)";
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum DispatchErrorKind {
     UnknownRequest,
     NoSuchFile,
@@ -50,6 +51,9 @@ pub enum Error {
     Os(std::io::Error),
     Dispatch(DispatchErrorKind),
     JsonParsing,
+    MissingEnv(&'static str),
+    /// Not applied because another screen of the batch failed
+    Aborted,
 }
 
 impl fmt::Display for Error {
@@ -58,34 +62,8 @@ impl fmt::Display for Error {
             Self::Os(e) => write!(f, "Os error: {e}"),
             Self::Dispatch(e) => write!(f, "Dispatch error: {e}"),
             Self::JsonParsing => write!(f, "Json parsing error"),
-        }
-    }
-}
-
-#[derive(PartialEq)]
-pub enum Mode {
-    Default,
-    Contain,
-    Tile,
-}
-
-impl fmt::Display for Mode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::Default => write!(f, "default"),
-            Self::Contain => write!(f, "contain"),
-            Self::Tile => write!(f, "tile"),
-        }
-    }
-}
-
-impl Mode {
-    pub fn from_string(string: String) -> Self {
-        match string.to_lowercase().as_str() {
-            "default" => Mode::Default,
-            "contain" => Mode::Contain,
-            "tile" => Mode::Tile,
-            _ => Mode::Default,
+            Self::MissingEnv(name) => write!(f, "Environment variable '{name}' is not set"),
+            Self::Aborted => write!(f, "Not applied, another screen failed"),
         }
     }
 }
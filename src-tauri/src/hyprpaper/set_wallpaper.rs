@@ -1,94 +1,219 @@
-use super::{
-    active_screens, unload, DispatchErrorKind, Error, Mode, Unload, HYPRCTL_CMD, HYPRPAPER_CMD,
-    NO_SUCH_FILE_ERROR, UNKNOWN_REQUEST_ERROR, WALLPAPER_NOT_PRELOADED,
-};
-use std::process;
+use super::socket::{parse_reply, request, Socket};
+use super::{active_screens, preload, DispatchErrorKind, Error};
+use crate::backend::{Assignment, Mode};
+use crate::config;
+use std::collections::{HashMap, HashSet};
+
+type ScreenResults = Vec<(String, Result<(), Error>)>;
 
 pub fn set_wallpaper(screen: String, wallpaper: String, mode: &Mode) -> Result<(), Error> {
-    let mode_string = mode.to_string();
+    let screens = if screen == "all" {
+        active_screens()?
+    } else {
+        vec![screen]
+    };
+
+    let assignments: Vec<Assignment> = screens
+        .into_iter()
+        .map(|x| Assignment::new(x, wallpaper.clone(), mode.clone()))
+        .collect();
+
+    for (_, result) in set_wallpapers(&assignments)? {
+        result?;
+    }
+
+    Ok(())
+}
 
-    if !std::path::Path::new(&wallpaper).exists() {
-        log::error!("Wallpaper not found, '{wallpaper}'");
-        return Err(Error::Dispatch(DispatchErrorKind::NoSuchFile));
+/// Sets every assignment or none of them.
+///
+/// Every file is checked and preloaded before the first `wallpaper` request, and when a
+/// request still fails the screens set before it get their previous wallpaper back. The
+/// failing screen reports why, the others [`Error::Aborted`]. Unlike `reload`, `wallpaper`
+/// doesn't unload anything, so hyprpaper doesn't need a pause between screens. Connection
+/// failures abort the batch as a whole.
+pub fn set_wallpapers(assignments: &[Assignment]) -> Result<ScreenResults, Error> {
+    if let Some((failed, kind)) = check(assignments)? {
+        return Ok(aborted(assignments, failed, kind));
     }
 
-    unload(Unload::All)?;
+    let previous = match active_wallpapers() {
+        Ok(v) => v,
+        Err(Error::Dispatch(kind)) => {
+            log::warn!("Failed to list active wallpapers, a failed batch can't be undone: {kind}");
+            HashMap::new()
+        }
+        Err(e) => return Err(e),
+    };
+
+    for (i, assignment) in assignments.iter().enumerate() {
+        // Anything else reaches hyprpaper rendered to the screen size
+        let mode_prefix = match assignment.mode {
+            Mode::Contain(_) => Some("contain"),
+            Mode::Tile => Some("tile"),
+            _ => None,
+        };
 
-    let mut screens: Vec<String> = Vec::new();
+        if let Err(kind) = send(&assignment.screen, &assignment.wallpaper, mode_prefix)? {
+            log::error!(
+                "Failed to set wallpaper('{}', '{}', '{}'): {kind}",
+                assignment.screen,
+                assignment.wallpaper,
+                assignment.mode
+            );
 
-    if screen == "all" {
-        screens = active_screens()?;
-    } else {
-        screens.push(screen);
+            restore(&assignments[..i], &previous);
+            return Ok(aborted(assignments, i, kind));
+        }
     }
 
-    for target_screen in screens {
-        let mut wallpaper_command_value = String::new();
+    Ok(assignments
+        .iter()
+        .map(|a| (a.screen.clone(), Ok(())))
+        .collect())
+}
+
+/// Index and error of the first assignment whose file is missing or fails to preload.
+/// Every distinct file is preloaded once.
+fn check(assignments: &[Assignment]) -> Result<Option<(usize, DispatchErrorKind)>, Error> {
+    let mut preloaded: HashSet<&str> = HashSet::new();
+
+    for (i, assignment) in assignments.iter().enumerate() {
+        let wallpaper = &assignment.wallpaper;
 
-        wallpaper_command_value.push_str(&target_screen);
-        wallpaper_command_value.push(',');
-        if mode != &Mode::Default {
-            wallpaper_command_value.push_str(&mode_string);
-            wallpaper_command_value.push(':');
+        if !std::path::Path::new(wallpaper).exists() {
+            log::error!("Wallpaper not found, '{wallpaper}'");
+            return Ok(Some((i, DispatchErrorKind::NoSuchFile)));
         }
-        wallpaper_command_value.push_str(&wallpaper);
-
-        let cmd = process::Command::new(HYPRCTL_CMD)
-            .args([HYPRPAPER_CMD, "reload", &wallpaper_command_value])
-            .output();
-
-        match cmd {
-            Ok(output) => {
-                if let Ok(text) = String::from_utf8(output.stdout.clone()) {
-                    if text == UNKNOWN_REQUEST_ERROR {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): unknown request"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::UnknownRequest));
-                    } else if text.starts_with(NO_SUCH_FILE_ERROR) {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): no such file"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::NoSuchFile));
-                    } else if text == WALLPAPER_NOT_PRELOADED {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): wallpaper not preloaded"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::WallpaperNotPreloaded));
-                    } else if text.contains("no such file:") {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): no such file"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::NoSuchFile));
-                    } else if text.starts_with("Couldn't connect to") {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): sock connection failed"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::SockConnectionFailed));
-                    }
-
-                    if text != "ok\n" {
-                        log::error!(
-                            "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): Unexpected: {text}"
-                        );
-                        return Err(Error::Dispatch(DispatchErrorKind::UnExpected));
-                    }
-                }
-            }
-            Err(e) => {
-                log::error!(
-                    "Failed to set wallpaper('{target_screen}', '{wallpaper}', '{mode_string}'): {e}"
-                );
-                return Err(Error::Os(e));
-            }
+
+        if !preloaded.insert(wallpaper) {
+            continue;
         }
 
-        // hyprpaper will crash if you don't wait after setting the first wallpaper
-        // TODO: pass screens Vec and use `hyprctl --batch`
-        // https://wiki.hypr.land/Configuring/Using-hyprctl/#batch
-        // hyprctl --batch "keyword general:border_size 2 ; keyword general:gaps_out 20"
-        std::thread::sleep(std::time::Duration::from_millis(100));
+        match preload(wallpaper.clone()) {
+            Ok(_) => {}
+            Err(Error::Dispatch(kind)) => return Ok(Some((i, kind))),
+            Err(e) => return Err(e),
+        }
     }
 
-    Ok(())
+    Ok(None)
+}
+
+/// Sends one `wallpaper` request, the outer error is the connection's.
+fn send(
+    screen: &str,
+    wallpaper: &str,
+    mode_prefix: Option<&str>,
+) -> Result<Result<(), DispatchErrorKind>, Error> {
+    let mut wallpaper_command_value = String::new();
+
+    wallpaper_command_value.push_str(screen);
+    wallpaper_command_value.push(',');
+    if let Some(prefix) = mode_prefix {
+        wallpaper_command_value.push_str(prefix);
+        wallpaper_command_value.push(':');
+    }
+    wallpaper_command_value.push_str(wallpaper);
+
+    let reply = request(
+        Socket::Hyprpaper,
+        &format!(
+            "{} {wallpaper_command_value}",
+            config::get().hyprpaper.wallpaper
+        ),
+    )?;
+
+    Ok(parse_reply(&reply))
+}
+
+/// Wallpaper of each screen, from `listactive`.
+fn active_wallpapers() -> Result<HashMap<String, String>, Error> {
+    let reply = request(Socket::Hyprpaper, &config::get().hyprpaper.listactive)?;
+
+    if reply.trim_end() == super::UNKNOWN_REQUEST_ERROR {
+        return Err(Error::Dispatch(DispatchErrorKind::UnknownRequest));
+    }
+
+    Ok(parse_active(&reply))
+}
+
+/// `<screen> = <path>` lines, hyprpaper answers `none` when nothing is set.
+fn parse_active(reply: &str) -> HashMap<String, String> {
+    reply
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(screen, path)| (screen.trim().to_string(), path.trim().to_string()))
+        .collect()
+}
+
+/// Puts back the wallpapers `previous` had for the screens of `done`. The previous files
+/// are still loaded since nothing was unloaded in between.
+fn restore(done: &[Assignment], previous: &HashMap<String, String>) {
+    for assignment in done {
+        let Some(wallpaper) = previous.get(&assignment.screen) else {
+            continue;
+        };
+
+        match send(&assignment.screen, wallpaper, None) {
+            Ok(Ok(_)) => {}
+            Ok(Err(kind)) => log::warn!(
+                "Failed to restore wallpaper('{}', '{wallpaper}'): {kind}",
+                assignment.screen
+            ),
+            Err(e) => log::warn!(
+                "Failed to restore wallpaper('{}', '{wallpaper}'): {e}",
+                assignment.screen
+            ),
+        }
+    }
+}
+
+/// Results of a batch that failed at `failed`.
+fn aborted(assignments: &[Assignment], failed: usize, kind: DispatchErrorKind) -> ScreenResults {
+    assignments
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            let error = if i == failed {
+                Error::Dispatch(kind)
+            } else {
+                Error::Aborted
+            };
+
+            (a.screen.clone(), Err(error))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listactive_reply() {
+        let active = parse_active("DP-1 = /a/b c.png\nHDMI-A-1 = /d.jpg\n");
+
+        assert_eq!(active.len(), 2);
+        assert_eq!(active["DP-1"], "/a/b c.png");
+        assert_eq!(active["HDMI-A-1"], "/d.jpg");
+        assert!(parse_active("none").is_empty());
+    }
+
+    #[test]
+    fn aborted_batch_fails_every_screen() {
+        let assignments: Vec<Assignment> = ["DP-1", "DP-2", "DP-3"]
+            .into_iter()
+            .map(|x| Assignment::new(x.to_string(), String::from("/a.png"), Mode::Default))
+            .collect();
+
+        let results = aborted(&assignments, 1, DispatchErrorKind::NoSuchFile);
+
+        assert!(matches!(results[0].1, Err(Error::Aborted)));
+        assert!(matches!(
+            results[1].1,
+            Err(Error::Dispatch(DispatchErrorKind::NoSuchFile))
+        ));
+        assert!(matches!(results[2].1, Err(Error::Aborted)));
+    }
 }
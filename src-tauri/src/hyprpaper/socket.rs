@@ -0,0 +1,188 @@
+use super::{DispatchErrorKind, Error};
+use crate::config;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const HYPRLAND_SOCKET: &str = ".socket.sock";
+const HYPRLAND_EVENTS_SOCKET: &str = ".socket2.sock";
+
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub enum Socket {
+    Hyprpaper,
+    Hyprland,
+    HyprlandEvents,
+}
+
+impl Socket {
+    fn file_name(&self) -> &'static str {
+        match self {
+            Self::Hyprpaper => &config::get().hyprpaper.socket,
+            Self::Hyprland => HYPRLAND_SOCKET,
+            Self::HyprlandEvents => HYPRLAND_EVENTS_SOCKET,
+        }
+    }
+
+    /// `$XDG_RUNTIME_DIR/hypr/$HYPRLAND_INSTANCE_SIGNATURE/<socket>`, falling back to
+    /// `/tmp/hypr/...` which older Hyprland releases used.
+    pub fn path(&self) -> Result<PathBuf, Error> {
+        let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+            .map_err(|_| Error::MissingEnv("HYPRLAND_INSTANCE_SIGNATURE"))?;
+
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+            let path = Path::new(&runtime_dir)
+                .join("hypr")
+                .join(&signature)
+                .join(self.file_name());
+
+            if path.exists() {
+                return Ok(path);
+            }
+        }
+
+        let legacy_path = Path::new("/tmp/hypr")
+            .join(&signature)
+            .join(self.file_name());
+
+        if legacy_path.exists() {
+            return Ok(legacy_path);
+        }
+
+        log::error!("Socket '{}' not found", self.file_name());
+        Err(Error::Dispatch(DispatchErrorKind::SockConnectionFailed))
+    }
+}
+
+/// Sends `command` to the socket and returns the raw reply.
+pub fn request(socket: Socket, command: &str) -> Result<String, Error> {
+    request_at(&socket.path()?, command)
+}
+
+/// Same as [`request`] but with an explicit socket path.
+pub fn request_at(path: &Path, command: &str) -> Result<String, Error> {
+    let mut stream = match UnixStream::connect(path) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("Failed to connect to '{}': {e}", path.to_string_lossy());
+            return Err(Error::Dispatch(DispatchErrorKind::SockConnectionFailed));
+        }
+    };
+
+    stream
+        .set_read_timeout(Some(SOCKET_TIMEOUT))
+        .map_err(Error::Os)?;
+    stream
+        .set_write_timeout(Some(SOCKET_TIMEOUT))
+        .map_err(Error::Os)?;
+
+    stream.write_all(command.as_bytes()).map_err(Error::Os)?;
+
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).map_err(Error::Os)?;
+
+    log::trace!(
+        "'{}' <- '{command}' -> '{}'",
+        path.to_string_lossy(),
+        reply.trim_end()
+    );
+
+    Ok(reply)
+}
+
+/// Maps a hyprpaper reply to a typed result.
+pub fn parse_reply(reply: &str) -> Result<(), DispatchErrorKind> {
+    let text = reply.trim_end();
+
+    if text == "ok" {
+        Ok(())
+    } else if text == super::UNKNOWN_REQUEST_ERROR {
+        Err(DispatchErrorKind::UnknownRequest)
+    } else if text.contains(super::NO_SUCH_FILE_ERROR) {
+        Err(DispatchErrorKind::NoSuchFile)
+    } else if text == super::WALLPAPER_NOT_PRELOADED {
+        Err(DispatchErrorKind::WallpaperNotPreloaded)
+    } else {
+        Err(DispatchErrorKind::UnExpected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread::JoinHandle;
+
+    /// Answers one connection with `reply`, returning the request it got.
+    fn fake_server(path: &Path, reply: &'static str) -> JoinHandle<String> {
+        let listener = UnixListener::bind(path).unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        })
+    }
+
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "hypr-nitrogen-test-{}-{name}.sock",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn request_sends_command_and_reads_reply() {
+        let path = socket_path("ok");
+        let server = fake_server(&path, "ok\n");
+
+        let reply = request_at(&path, "wallpaper DP-1,/a.png").unwrap();
+
+        assert_eq!(server.join().unwrap(), "wallpaper DP-1,/a.png");
+        assert!(parse_reply(&reply).is_ok());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn request_without_server() {
+        let path = socket_path("missing");
+
+        assert!(matches!(
+            request_at(&path, "preload /a.png"),
+            Err(Error::Dispatch(DispatchErrorKind::SockConnectionFailed))
+        ));
+    }
+
+    #[test]
+    fn hyprpaper_errors() {
+        let path = socket_path("error");
+        let server = fake_server(&path, "wallpaper failed (not preloaded)\n");
+
+        let reply = request_at(&path, "wallpaper DP-1,/a.png").unwrap();
+        server.join().unwrap();
+
+        assert!(matches!(
+            parse_reply(&reply),
+            Err(DispatchErrorKind::WallpaperNotPreloaded)
+        ));
+        assert!(matches!(
+            parse_reply("unknown request"),
+            Err(DispatchErrorKind::UnknownRequest)
+        ));
+        assert!(matches!(
+            parse_reply("preload failed: no such file: /a.png"),
+            Err(DispatchErrorKind::NoSuchFile)
+        ));
+        assert!(matches!(
+            parse_reply("something else"),
+            Err(DispatchErrorKind::UnExpected)
+        ));
+        let _ = std::fs::remove_file(&path);
+    }
+}
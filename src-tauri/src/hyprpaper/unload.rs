@@ -1,34 +1,16 @@
-use super::{DispatchErrorKind, Error, Unload, HYPRCTL_CMD, HYPRPAPER_CMD, UNKNOWN_REQUEST_ERROR};
-use std::process;
+use super::socket::{parse_reply, request, Socket};
+use super::{Error, Unload};
+use crate::config;
 
 pub fn unload(action: Unload) -> Result<(), Error> {
     let action_string = action.to_string();
 
-    match process::Command::new(HYPRCTL_CMD)
-        .args([HYPRPAPER_CMD, "unload", &action_string])
-        .output()
-    {
-        Ok(output) => {
-            if let Ok(text) = String::from_utf8(output.stdout.clone()) {
-                if text == UNKNOWN_REQUEST_ERROR {
-                    log::error!(
-                        "Failed to unload wallpaper '{action_string}': unknown request"
-                    );
-                    return Err(Error::Dispatch(DispatchErrorKind::UnknownRequest));
-                }
+    let verb = &config::get().hyprpaper.unload;
+    let reply = request(Socket::Hyprpaper, &format!("{verb} {action_string}"))?;
 
-                if text != "ok\n" {
-                    log::error!(
-                        "Failed to unload wallpaper '{action_string}': Unexpected: {text}"
-                    );
-                    return Err(Error::Dispatch(DispatchErrorKind::UnExpected));
-                }
-            }
-        }
-        Err(e) => {
-            log::error!("Failed to unload wallpaper '{action_string}': {e}");
-            return Err(Error::Os(e));
-        }
+    if let Err(kind) = parse_reply(&reply) {
+        log::error!("Failed to unload wallpaper '{action_string}': {kind}");
+        return Err(Error::Dispatch(kind));
     }
 
     Ok(())
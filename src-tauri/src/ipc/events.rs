@@ -0,0 +1,66 @@
+use serde::Serialize;
+use std::fmt;
+use ts_rs::TS;
+
+/// Emitted when a screen is connected or disconnected.
+pub const MONITORS_CHANGED: &str = "monitors-changed";
+
+/// Emitted when the backend changed `active` rows on its own, e.g. a rotation tick.
+pub const ACTIVE_CHANGED: &str = "active-changed";
+
+/// Emitted when the source watcher added, updated or removed wallpapers.
+pub const WALLPAPERS_CHANGED: &str = "wallpapers-changed";
+
+#[derive(TS, Serialize, Clone)]
+#[ts(export)]
+pub struct MonitorEvent {
+    pub screen: String,
+    pub connected: bool,
+}
+
+/// Emitted while `cmd_scan_source` or `cmd_scan_all_sources` runs, at most every 100ms.
+pub const SCAN_PROGRESS: &str = "scan-progress";
+
+#[derive(TS, Serialize, Clone, Copy, Default, PartialEq, Debug)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStage {
+    #[default]
+    Walking,
+    Thumbnails,
+    Done,
+    Cancelled,
+    /// Stopped by an error, which `last_error` holds
+    Failed,
+}
+
+impl fmt::Display for ScanStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Walking => write!(f, "walking"),
+            Self::Thumbnails => write!(f, "thumbnails"),
+            Self::Done => write!(f, "done"),
+            Self::Cancelled => write!(f, "cancelled"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Counters are totals of the whole run, across sources for `cmd_scan_all_sources`.
+#[derive(TS, Serialize, Clone, Default, Debug)]
+#[ts(export)]
+pub struct ScanProgress {
+    pub stage: ScanStage,
+    /// Source currently being walked
+    pub source_id: Option<String>,
+    /// Image files found
+    pub discovered: u32,
+    /// New or changed files, unchanged ones aren't hashed again
+    pub hashed: u32,
+    pub inserted: u32,
+    pub removed: u32,
+    pub thumbnails_total: u32,
+    pub thumbnails_done: u32,
+    pub errors: u32,
+    pub last_error: Option<String>,
+}
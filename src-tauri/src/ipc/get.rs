@@ -1,14 +1,21 @@
+use crate::commands;
 use crate::database::connection::DbPoolWrapper;
 use crate::database::models::*;
-use crate::hyprpaper;
-use crate::ipc::Response;
-use crate::schema;
-use diesel::prelude::*;
+use crate::ipc::{Response, WallpaperFilter, WallpaperSearch};
+use crate::utils::phash;
+use std::collections::HashMap;
 use tauri::State;
 
 #[tauri::command]
-pub async fn cmd_get_screens() -> Result<Response<Vec<String>>, String> {
-    match hyprpaper::active_screens() {
+pub async fn cmd_get_screens(
+    state: State<'_, DbPoolWrapper>,
+) -> Result<Response<Vec<String>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_screens(&mut conn) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -23,7 +30,7 @@ pub async fn cmd_get_wallpaper_sources(
         Err(e) => return Err(e.to_string()),
     };
 
-    match schema::wallpaper_sources::table.get_results::<WallpaperSource>(&mut conn) {
+    match commands::get_wallpaper_sources(&mut conn) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -32,21 +39,30 @@ pub async fn cmd_get_wallpaper_sources(
 #[tauri::command]
 pub async fn cmd_get_wallpapers(
     state: State<'_, DbPoolWrapper>,
+    filter: Option<WallpaperFilter>,
 ) -> Result<Response<Vec<Wallpaper>>, String> {
     let mut conn = match state.pool.get() {
         Ok(conn) => conn,
         Err(e) => return Err(e.to_string()),
     };
 
-    match schema::wallpapers::table
-        .inner_join(
-            schema::wallpaper_sources::table
-                .on(schema::wallpapers::wallpaper_source_id.eq(schema::wallpaper_sources::id)),
-        )
-        .filter(schema::wallpaper_sources::active.eq(true))
-        .select(schema::wallpapers::all_columns)
-        .get_results::<Wallpaper>(&mut conn)
-    {
+    match commands::get_wallpapers(&mut conn, &filter.unwrap_or_default()) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_search_wallpapers(
+    state: State<'_, DbPoolWrapper>,
+    search: Option<WallpaperSearch>,
+) -> Result<Response<WallpaperPage>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::search_wallpapers(&mut conn, &search.unwrap_or_default()) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -61,7 +77,135 @@ pub async fn cmd_get_active_wallpapers(
         Err(e) => return Err(e.to_string()),
     };
 
-    match schema::active::table.get_results::<Active>(&mut conn) {
+    match commands::get_active_wallpapers(&mut conn) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_settings(
+    state: State<'_, DbPoolWrapper>,
+) -> Result<Response<HashMap<String, String>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_settings(&mut conn) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_rotations(
+    state: State<'_, DbPoolWrapper>,
+) -> Result<Response<Vec<Rotation>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_rotations(&mut conn) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_history(
+    state: State<'_, DbPoolWrapper>,
+    screen: Option<String>,
+    limit: i64,
+) -> Result<Response<Vec<History>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_history(&mut conn, screen, limit) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Near-duplicate clusters, `max_distance` defaults to 10 of the 64 hash bits.
+#[tauri::command]
+pub async fn cmd_get_duplicates(
+    state: State<'_, DbPoolWrapper>,
+    max_distance: Option<u32>,
+) -> Result<Response<Vec<DuplicateCluster>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let max_distance = max_distance.unwrap_or(phash::DEFAULT_MAX_DISTANCE);
+
+    match commands::get_duplicates(&mut conn, max_distance) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_collections(
+    state: State<'_, DbPoolWrapper>,
+) -> Result<Response<Vec<Collection>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_collections(&mut conn) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_collection_wallpapers(
+    state: State<'_, DbPoolWrapper>,
+    collection_id: String,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_collection_wallpapers(&mut conn, &collection_id) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_tags(
+    state: State<'_, DbPoolWrapper>,
+) -> Result<Response<Vec<TagCount>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_tags(&mut conn) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_get_wallpaper_tags(
+    state: State<'_, DbPoolWrapper>,
+    wallpaper_id: String,
+) -> Result<Response<Vec<WallpaperTag>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::get_wallpaper_tags(&mut conn, &wallpaper_id) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
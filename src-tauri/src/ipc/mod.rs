@@ -1,3 +1,4 @@
+pub mod events;
 mod get;
 mod remove;
 mod set;
@@ -8,7 +9,9 @@ pub use remove::*;
 pub use set::*;
 pub use util::*;
 
-use serde::Serialize;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
 
 #[derive(Serialize)]
 pub struct Response<T> {
@@ -20,3 +23,116 @@ impl<T> Response<T> {
         Self { data }
     }
 }
+
+#[derive(TS, Deserialize)]
+#[ts(export)]
+pub struct ScreenAssignment {
+    pub screen: String,
+    pub wallpaper_id: String,
+    pub mode: String,
+}
+
+/// Optional constraints for `cmd_get_wallpapers`, unset fields don't filter. Wallpapers
+/// scanned before dimensions were stored only match an empty filter.
+#[derive(TS, Deserialize, Args, Default, Debug)]
+#[ts(export, optional_fields)]
+#[serde(default)]
+pub struct WallpaperFilter {
+    #[arg(long)]
+    pub min_width: Option<i32>,
+    #[arg(long)]
+    pub max_width: Option<i32>,
+    #[arg(long)]
+    pub min_height: Option<i32>,
+    #[arg(long)]
+    pub max_height: Option<i32>,
+    /// e.g. `2.3` for ultrawide
+    #[arg(long)]
+    pub min_aspect_ratio: Option<f64>,
+    #[arg(long)]
+    pub max_aspect_ratio: Option<f64>,
+    /// Bytes
+    #[arg(long)]
+    pub min_file_size: Option<i64>,
+    /// Bytes
+    #[arg(long)]
+    pub max_file_size: Option<i64>,
+    /// Tag query, e.g. `nature AND NOT (snow OR winter)`
+    #[arg(long)]
+    pub tags: Option<String>,
+}
+
+#[derive(TS, Deserialize, ValueEnum, Default, Clone, Copy, Debug, PartialEq)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    #[default]
+    Path,
+    /// Last modification of the file
+    Modified,
+    /// File size
+    Size,
+    /// Width times height
+    Resolution,
+}
+
+#[derive(TS, Deserialize, ValueEnum, Clone, Copy, Debug, PartialEq)]
+#[ts(export)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+/// One page of `cmd_search_wallpapers`, unset fields don't filter. Pass the `next_cursor` of
+/// a page, with everything else unchanged, to get the next one.
+#[derive(TS, Deserialize, Args, Default, Debug)]
+#[ts(export, optional_fields)]
+#[serde(default)]
+pub struct WallpaperSearch {
+    /// Words found in the keywords, file name, caption or category, each as a prefix
+    pub text: Option<String>,
+    #[arg(long)]
+    pub source_id: Option<String>,
+    /// Only favorites
+    #[arg(long)]
+    pub favorites: bool,
+    /// e.g. `3840x2160`
+    #[arg(long)]
+    pub resolution: Option<String>,
+    #[arg(long, value_enum)]
+    pub orientation: Option<Orientation>,
+    /// Category from the metadata file, the whole of it
+    #[arg(long)]
+    pub category: Option<String>,
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: SearchSort,
+    #[arg(long)]
+    pub descending: bool,
+    /// Favorites before the other wallpapers, each sorted by `sort`
+    #[arg(long)]
+    pub favorites_first: bool,
+    #[arg(long)]
+    pub cursor: Option<String>,
+    /// Wallpapers per page, 100 by default and at most 1000
+    #[arg(long)]
+    pub limit: Option<u32>,
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub filter: WallpaperFilter,
+}
+
+/// Outcome of one screen in a batch, `error` is `None` on success.
+#[derive(TS, Serialize)]
+#[ts(export)]
+pub struct ScreenResult {
+    pub screen: String,
+    pub error: Option<String>,
+}
+
+impl ScreenResult {
+    pub fn new(screen: String, error: Option<String>) -> Self {
+        Self { screen, error }
+    }
+}
@@ -1,8 +1,7 @@
+use crate::commands;
 use crate::database::connection::DbPoolWrapper;
 use crate::database::models::*;
 use crate::ipc::Response;
-use crate::schema;
-use diesel::prelude::*;
 use tauri::State;
 
 #[tauri::command]
@@ -15,11 +14,73 @@ pub async fn cmd_remove_wallpaper_source(
         Err(e) => return Err(e.to_string()),
     };
 
-    match diesel::delete(
-        schema::wallpaper_sources::table.filter(schema::wallpaper_sources::id.eq(id)),
-    )
-    .get_result(&mut conn)
-    {
+    match commands::remove_wallpaper_source(&mut conn, id) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_remove_rotation(
+    state: State<'_, DbPoolWrapper>,
+    screen: String,
+) -> Result<Response<Rotation>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::remove_rotation(&mut conn, screen) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_remove_collection(
+    state: State<'_, DbPoolWrapper>,
+    id: String,
+) -> Result<Response<Collection>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::remove_collection(&mut conn, id) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_remove_from_collection(
+    state: State<'_, DbPoolWrapper>,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::remove_from_collection(&mut conn, collection_id, wallpaper_ids) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_remove_tags(
+    state: State<'_, DbPoolWrapper>,
+    wallpaper_ids: Vec<String>,
+    tags: Vec<String>,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::remove_tags(&mut conn, wallpaper_ids, tags) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -1,11 +1,8 @@
+use crate::commands;
 use crate::database::connection::DbPoolWrapper;
+use crate::database::history::Direction;
 use crate::database::models::*;
-use crate::hyprpaper;
-use crate::ipc::Response;
-use crate::schema;
-use diesel::prelude::*;
-use diesel::upsert::excluded;
-use rand::Rng;
+use crate::ipc::{Response, ScreenAssignment, ScreenResult};
 use tauri::State;
 
 #[tauri::command]
@@ -13,6 +10,7 @@ pub async fn cmd_set_wallpaper(
     state: State<'_, DbPoolWrapper>,
     screen: String,
     wallpaper_id: Option<String>,
+    collection_id: Option<String>,
     mode: String,
     is_temporary: bool,
 ) -> Result<Response<Wallpaper>, String> {
@@ -21,99 +19,34 @@ pub async fn cmd_set_wallpaper(
         Err(e) => return Err(e.to_string()),
     };
 
-    match hyprpaper::unload(hyprpaper::Unload::All) {
-        Ok(_) => {}
-        Err(e) => return Err(e.to_string()),
+    match commands::set_wallpaper(
+        &mut conn,
+        screen,
+        wallpaper_id,
+        collection_id,
+        mode,
+        is_temporary,
+    ) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
     }
+}
 
-    let wallpaper: Option<Wallpaper> = match wallpaper_id {
-        Some(id) => {
-            if id.is_empty() {
-                return Err(String::from("Invalid wallpaper id"));
-            }
-
-            match schema::wallpapers::table
-                .filter(schema::wallpapers::id.eq(&id))
-                .get_result::<Wallpaper>(&mut conn)
-            {
-                Ok(v) => Some(v),
-                Err(e) => return Err(e.to_string()),
-            }
-        }
-        None => match schema::wallpapers::table
-            .inner_join(schema::wallpaper_sources::table)
-            .filter(schema::wallpaper_sources::dsl::active.eq(true))
-            .select(schema::wallpapers::all_columns)
-            .get_results::<Wallpaper>(&mut conn)
-        {
-            Ok(v) => {
-                if v.is_empty() {
-                    return Err(String::from("No wallpapers found"));
-                }
-
-                let mut rng = rand::rng();
-                let r = rng.random_range(0..v.len());
-
-                v.get(r).cloned()
-            }
-            Err(e) => return Err(e.to_string()),
-        },
+#[tauri::command]
+pub async fn cmd_set_wallpapers(
+    state: State<'_, DbPoolWrapper>,
+    assignments: Vec<ScreenAssignment>,
+    is_temporary: bool,
+) -> Result<Response<Vec<ScreenResult>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
     };
 
-    if let Some(target_wallpaper) = wallpaper {
-        let h_mode = hyprpaper::Mode::from_string(mode);
-
-        match hyprpaper::set_wallpaper(screen.clone(), target_wallpaper.path.clone(), &h_mode) {
-            Ok(_) => {
-                if !is_temporary {
-                    let mut actives_list: Vec<NewActive> = Vec::new();
-
-                    if screen == "all" {
-                        match hyprpaper::active_screens() {
-                            Ok(v) => {
-                                for a in v {
-                                    actives_list.push(NewActive::new(
-                                        a,
-                                        target_wallpaper.id.clone(),
-                                        h_mode.to_string(),
-                                    ));
-                                }
-                            }
-                            Err(e) => return Err(e.to_string()),
-                        }
-                    } else {
-                        actives_list.push(NewActive::new(
-                            screen,
-                            target_wallpaper.id.clone(),
-                            h_mode.to_string(),
-                        ));
-                    }
-
-                    for active in actives_list {
-                        match diesel::insert_into(schema::active::table)
-                            .values(&active)
-                            .on_conflict(schema::active::dsl::screen)
-                            .do_update()
-                            .set((
-                                schema::active::dsl::wallpaper_id
-                                    .eq(excluded(schema::active::dsl::wallpaper_id)),
-                                schema::active::dsl::mode.eq(excluded(schema::active::dsl::mode)),
-                            ))
-                            .execute(&mut conn)
-                        {
-                            Ok(_) => {}
-                            Err(e) => return Err(e.to_string()),
-                        };
-                    }
-                }
-            }
-            Err(e) => return Err(e.to_string()),
-        };
-
-        return Ok(Response::new(target_wallpaper));
+    match commands::set_wallpapers(&mut conn, assignments, is_temporary) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
     }
-
-    Err(String::from("Failed to set wallpaper"))
 }
 
 #[tauri::command]
@@ -125,12 +58,8 @@ pub async fn cmd_add_wallpaper_source(
         Ok(conn) => conn,
         Err(e) => return Err(e.to_string()),
     };
-    let wallpaper_source = NewWallpaperSource::new(path);
 
-    match diesel::insert_into(schema::wallpaper_sources::table)
-        .values(&wallpaper_source)
-        .get_result::<WallpaperSource>(&mut conn)
-    {
+    match commands::add_wallpaper_source(&mut conn, path) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -147,10 +76,7 @@ pub async fn cmd_update_wallpaper_source_active(
         Err(e) => return Err(e.to_string()),
     };
 
-    match diesel::update(schema::wallpaper_sources::table.find(id))
-        .set(schema::wallpaper_sources::active.eq(active))
-        .get_result(&mut conn)
-    {
+    match commands::update_wallpaper_source_active(&mut conn, id, active) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
@@ -167,11 +93,191 @@ pub async fn cmd_update_wallpaper_favorite(
         Err(e) => return Err(e.to_string()),
     };
 
-    match diesel::update(schema::wallpapers::table)
-        .filter(schema::wallpapers::id.eq(&id))
-        .set(schema::wallpapers::dsl::is_favorite.eq(new_value))
-        .get_result(&mut conn)
-    {
+    match commands::update_wallpaper_favorite(&mut conn, id, new_value) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_set_setting(
+    state: State<'_, DbPoolWrapper>,
+    key: String,
+    value: String,
+) -> Result<Response<String>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::set_setting(&mut conn, key, value) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_set_rotation(
+    state: State<'_, DbPoolWrapper>,
+    screen: String,
+    interval_secs: i32,
+    wallpaper_source_id: Option<String>,
+    collection_id: Option<String>,
+    favorites_only: bool,
+    shuffle: bool,
+    mode: String,
+    enabled: bool,
+) -> Result<Response<Rotation>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let rotation = NewRotation::new(
+        screen,
+        interval_secs,
+        wallpaper_source_id,
+        collection_id,
+        favorites_only,
+        shuffle,
+        mode,
+        enabled,
+    );
+
+    match commands::set_rotation(&mut conn, rotation) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_advance_rotation(
+    state: State<'_, DbPoolWrapper>,
+    screen: String,
+) -> Result<Response<Wallpaper>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::advance_rotation(&mut conn, screen) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_history_back(
+    state: State<'_, DbPoolWrapper>,
+    screen: String,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::step_history(&mut conn, screen, Direction::Back) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_history_forward(
+    state: State<'_, DbPoolWrapper>,
+    screen: String,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::step_history(&mut conn, screen, Direction::Forward) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_create_collection(
+    state: State<'_, DbPoolWrapper>,
+    name: String,
+) -> Result<Response<Collection>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::create_collection(&mut conn, name) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_rename_collection(
+    state: State<'_, DbPoolWrapper>,
+    id: String,
+    name: String,
+) -> Result<Response<Collection>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::rename_collection(&mut conn, id, name) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_add_to_collection(
+    state: State<'_, DbPoolWrapper>,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::add_to_collection(&mut conn, collection_id, wallpaper_ids) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_reorder_collection(
+    state: State<'_, DbPoolWrapper>,
+    collection_id: String,
+    wallpaper_ids: Vec<String>,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::reorder_collection(&mut conn, collection_id, wallpaper_ids) {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn cmd_add_tags(
+    state: State<'_, DbPoolWrapper>,
+    wallpaper_ids: Vec<String>,
+    tags: Vec<String>,
+) -> Result<Response<Vec<Wallpaper>>, String> {
+    let mut conn = match state.pool.get() {
+        Ok(conn) => conn,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    match commands::add_tags(&mut conn, wallpaper_ids, tags) {
         Ok(v) => Ok(Response::new(v)),
         Err(e) => Err(e.to_string()),
     }
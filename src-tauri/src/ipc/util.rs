@@ -1,59 +1,66 @@
+use crate::commands;
 use crate::database::connection::DbPoolWrapper;
 use crate::database::models::*;
+use crate::ipc::events::SCAN_PROGRESS;
 use crate::ipc::Response;
-use crate::schema;
 use crate::utils::restore;
-use crate::utils::scan::{scan, scan_all};
-use diesel::prelude::*;
-use tauri::State;
+use crate::utils::scan::{ScanContext, ScanControl};
+use tauri::{AppHandle, Emitter, State};
+
+fn scan_context(app: AppHandle, control: &ScanControl) -> ScanContext {
+    ScanContext::new(control, move |progress| {
+        if let Err(e) = app.emit(SCAN_PROGRESS, progress.clone()) {
+            log::error!("Failed to emit '{SCAN_PROGRESS}': {e}");
+        }
+    })
+}
 
 #[tauri::command]
 pub async fn cmd_scan_source(
+    app: AppHandle,
     state: State<'_, DbPoolWrapper>,
+    control: State<'_, ScanControl>,
     source_id: String,
 ) -> Result<Response<Vec<Wallpaper>>, String> {
-    use schema::wallpaper_sources::dsl::*;
-
     let mut conn = match state.pool.get() {
         Ok(conn) => conn,
         Err(e) => return Err(e.to_string()),
     };
 
-    let wallpaper_source = match wallpaper_sources
-        .find(source_id)
-        .get_result::<WallpaperSource>(&mut conn)
-    {
-        Ok(v) => v,
-        Err(e) => return Err(e.to_string()),
-    };
-
-    let wallpapers = match scan(&mut conn, wallpaper_source.id, wallpaper_source.path).await {
-        Ok(v) => v,
-        Err(e) => return Err(e),
-    };
+    let mut ctx = scan_context(app, &control);
 
-    Ok(Response::new(wallpapers))
+    match commands::scan_source(&mut conn, source_id, &mut ctx).await {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 #[tauri::command]
 pub async fn cmd_scan_all_sources(
+    app: AppHandle,
     state: State<'_, DbPoolWrapper>,
+    control: State<'_, ScanControl>,
 ) -> Result<Response<Vec<Wallpaper>>, String> {
     let mut conn = match state.pool.get() {
         Ok(conn) => conn,
         Err(e) => return Err(e.to_string()),
     };
 
-    if let Err(err) = diesel::delete(schema::wallpapers::table).execute(&mut conn) {
-        return Err(err.to_string());
-    }
+    let mut ctx = scan_context(app, &control);
 
-    match scan_all(&mut conn).await {
-        Ok(v) => Ok(v),
-        Err(e) => Err(e),
+    match commands::scan_all_sources(&mut conn, &mut ctx).await {
+        Ok(v) => Ok(Response::new(v)),
+        Err(e) => Err(e.to_string()),
     }
 }
 
+/// Stops running scans. Rows committed so far are kept, the scan commands resolve with
+/// what they got to. Returns false when no scan was running.
+#[tauri::command]
+pub async fn cmd_cancel_scan(control: State<'_, ScanControl>) -> Result<Response<bool>, String> {
+    Ok(Response::new(control.cancel()))
+}
+
 #[tauri::command]
 pub async fn cmd_restore_wallpapers(
     state: State<'_, DbPoolWrapper>,
@@ -65,6 +72,6 @@ pub async fn cmd_restore_wallpapers(
 
     match restore(&mut conn) {
         Ok(v) => Ok(Response::new(v)),
-        Err(e) => Err(e),
+        Err(e) => Err(e.to_string()),
     }
 }
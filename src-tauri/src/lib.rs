@@ -1,15 +1,21 @@
+mod backend;
 mod cli;
+mod commands;
+mod config;
 mod database;
+mod error;
 mod hyprpaper;
 mod ipc;
 mod schema;
 mod utils;
 
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Command};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
-use utils::restore;
+use ipc::events::{ACTIVE_CHANGED, WALLPAPERS_CHANGED};
+use tauri::Emitter;
+use utils::{hooks, hotplug, instance, restore, rotation, watcher};
 
 const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 const APP_NAME: &str = "hypr-nitrogen";
@@ -19,9 +25,13 @@ pub fn run() {
     dotenv().ok();
     let cli = Cli::parse();
 
+    let loaded = config::load(&config::path());
     let mut builder = env_logger::Builder::new();
 
-    builder.filter_level(log::LevelFilter::Info);
+    builder.filter_level(match &loaded {
+        Ok(v) => v.log_level.filter(),
+        Err(_) => config::LogLevel::default().filter(),
+    });
 
     if cli.verbose {
         builder.filter_level(log::LevelFilter::Trace);
@@ -29,6 +39,34 @@ pub fn run() {
 
     builder.init();
 
+    // Works with a broken configuration, `config check` is how it gets reported
+    let command = match cli.command {
+        Some(Command::Config { action }) => {
+            if let Err(e) = cli::run_config(action, cli.json) {
+                cli::print_error(&e, cli.json);
+                std::process::exit(e.exit_code());
+            }
+
+            std::process::exit(0);
+        }
+        command => command,
+    };
+
+    match loaded {
+        Ok(v) => config::init(v),
+        Err(e) => {
+            cli::print_error(&e, cli.json);
+            std::process::exit(e.exit_code());
+        }
+    }
+
+    // Only one GUI runs, everything but the daemon goes through it while it's open
+    if !cli.daemon {
+        if let Some(code) = instance::forward(cli.json) {
+            std::process::exit(code);
+        }
+    }
+
     let pool = database::connection::get_connection_pool();
 
     if let Ok(mut conn) = pool.get() {
@@ -39,42 +77,110 @@ pub fn run() {
     }
 
     if cli.restore {
-        if let Ok(mut conn) = pool.get() {
-            match restore(&mut conn) {
-                Ok(_) => log::info!("Wallpapers restored successfully"),
-                Err(e) => {
-                    log::error!("Failed to restore wallpapers: {e}");
-                    std::process::exit(1);
-                }
+        let result = pool
+            .get()
+            .map_err(error::Error::from)
+            .and_then(|mut conn| restore(&mut conn));
+        // Like `cli::run`, restore hooks would outlive the process without their timeout
+        hooks::wait();
+
+        match result {
+            Ok(_) => log::info!("Wallpapers restored successfully"),
+            Err(e) => {
+                cli::print_error(&e, cli.json);
+                std::process::exit(e.exit_code());
             }
         }
 
         std::process::exit(0);
     }
 
+    if cli.daemon {
+        log::info!("Running wallpaper rotations and source watcher");
+        watcher::spawn(pool.clone(), || {});
+        rotation::run(pool, || {});
+    }
+
+    if let Some(command) = command {
+        if let Err(e) = cli::run(command, &pool, cli.json) {
+            cli::print_error(&e, cli.json);
+            std::process::exit(e.exit_code());
+        }
+
+        std::process::exit(0);
+    }
+
     // NOTE:
     // Forces the appimage to use wayland and not xwayland
     //      https://github.com/tauri-apps/tauri/issues/11790
-    //      TODO: Add a clap flag to disable or enable this
-    std::env::set_var("GDK_BACKEND", "wayland");
+    if config::get().gui.force_wayland {
+        std::env::set_var("GDK_BACKEND", "wayland");
+    }
 
     tauri::Builder::default()
-        .manage(database::connection::DbPoolWrapper { pool })
+        .manage(database::connection::DbPoolWrapper { pool: pool.clone() })
+        .manage(utils::scan::ScanControl::default())
+        .setup(|app| {
+            let handle = app.handle().clone();
+
+            rotation::spawn(pool.clone(), move || {
+                if let Err(e) = handle.emit(ACTIVE_CHANGED, ()) {
+                    log::error!("Failed to emit '{ACTIVE_CHANGED}': {e}");
+                }
+            });
+
+            let handle = app.handle().clone();
+
+            watcher::spawn(pool.clone(), move || {
+                if let Err(e) = handle.emit(WALLPAPERS_CHANGED, ()) {
+                    log::error!("Failed to emit '{WALLPAPERS_CHANGED}': {e}");
+                }
+            });
+
+            instance::spawn_listener(app.handle().clone(), pool.clone());
+            hotplug::spawn_listener(app.handle().clone(), pool);
+            Ok(())
+        })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             ipc::cmd_set_wallpaper,
+            ipc::cmd_set_wallpapers,
             ipc::cmd_add_wallpaper_source,
             ipc::cmd_update_wallpaper_favorite,
             ipc::cmd_update_wallpaper_source_active,
             ipc::cmd_get_screens,
             ipc::cmd_get_wallpaper_sources,
             ipc::cmd_get_wallpapers,
+            ipc::cmd_search_wallpapers,
             ipc::cmd_get_active_wallpapers,
             ipc::cmd_remove_wallpaper_source,
             ipc::cmd_scan_source,
             ipc::cmd_scan_all_sources,
-            ipc::cmd_restore_wallpapers
+            ipc::cmd_cancel_scan,
+            ipc::cmd_restore_wallpapers,
+            ipc::cmd_get_settings,
+            ipc::cmd_set_setting,
+            ipc::cmd_get_rotations,
+            ipc::cmd_set_rotation,
+            ipc::cmd_advance_rotation,
+            ipc::cmd_remove_rotation,
+            ipc::cmd_get_history,
+            ipc::cmd_get_duplicates,
+            ipc::cmd_history_back,
+            ipc::cmd_history_forward,
+            ipc::cmd_get_collections,
+            ipc::cmd_get_collection_wallpapers,
+            ipc::cmd_create_collection,
+            ipc::cmd_rename_collection,
+            ipc::cmd_remove_collection,
+            ipc::cmd_add_to_collection,
+            ipc::cmd_remove_from_collection,
+            ipc::cmd_reorder_collection,
+            ipc::cmd_get_tags,
+            ipc::cmd_get_wallpaper_tags,
+            ipc::cmd_add_tags,
+            ipc::cmd_remove_tags
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -8,6 +8,85 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    collection_wallpapers (collection_id, wallpaper_id) {
+        collection_id -> Text,
+        wallpaper_id -> Text,
+        position -> Integer,
+    }
+}
+
+diesel::table! {
+    collections (id) {
+        id -> Text,
+        name -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    duplicates (path) {
+        path -> Text,
+        wallpaper_id -> Text,
+        wallpaper_source_id -> Text,
+        file_size -> Nullable<BigInt>,
+        mtime -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    history (id) {
+        id -> Integer,
+        screen -> Text,
+        wallpaper_id -> Text,
+        mode -> Text,
+        origin -> Text,
+        created_at -> BigInt,
+    }
+}
+
+diesel::table! {
+    history_cursor (screen) {
+        screen -> Text,
+        history_id -> Integer,
+    }
+}
+
+diesel::table! {
+    rotation_played (screen, wallpaper_id) {
+        screen -> Text,
+        wallpaper_id -> Text,
+    }
+}
+
+diesel::table! {
+    rotations (screen) {
+        screen -> Text,
+        interval_secs -> Integer,
+        wallpaper_source_id -> Nullable<Text>,
+        favorites_only -> Bool,
+        shuffle -> Bool,
+        mode -> Text,
+        enabled -> Bool,
+        last_changed_at -> BigInt,
+        collection_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
+    }
+}
+
 diesel::table! {
     wallpaper_sources (id) {
         id -> Text,
@@ -16,6 +95,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    wallpaper_tags (wallpaper_id, tag_id) {
+        wallpaper_id -> Text,
+        tag_id -> Integer,
+        origin -> Text,
+    }
+}
+
 diesel::table! {
     wallpapers (id) {
         id -> Text,
@@ -26,14 +113,45 @@ diesel::table! {
         resolution -> Nullable<Text>,
         wallpaper_source_id -> Text,
         keywords -> Nullable<Text>,
+        width -> Nullable<Integer>,
+        height -> Nullable<Integer>,
+        aspect_ratio -> Nullable<Double>,
+        file_size -> Nullable<BigInt>,
+        mtime -> Nullable<BigInt>,
+        phash -> Nullable<BigInt>,
+        palette -> Nullable<Text>,
+        caption -> Nullable<Text>,
+        category -> Nullable<Text>,
     }
 }
 
 diesel::joinable!(active -> wallpapers (wallpaper_id));
+diesel::joinable!(collection_wallpapers -> collections (collection_id));
+diesel::joinable!(collection_wallpapers -> wallpapers (wallpaper_id));
+diesel::joinable!(duplicates -> wallpaper_sources (wallpaper_source_id));
+diesel::joinable!(duplicates -> wallpapers (wallpaper_id));
+diesel::joinable!(history -> wallpapers (wallpaper_id));
+diesel::joinable!(history_cursor -> history (history_id));
+diesel::joinable!(rotation_played -> rotations (screen));
+diesel::joinable!(rotation_played -> wallpapers (wallpaper_id));
+diesel::joinable!(rotations -> collections (collection_id));
+diesel::joinable!(rotations -> wallpaper_sources (wallpaper_source_id));
+diesel::joinable!(wallpaper_tags -> tags (tag_id));
+diesel::joinable!(wallpaper_tags -> wallpapers (wallpaper_id));
 diesel::joinable!(wallpapers -> wallpaper_sources (wallpaper_source_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     active,
+    collection_wallpapers,
+    collections,
+    duplicates,
+    history,
+    history_cursor,
+    rotation_played,
+    rotations,
+    settings,
+    tags,
     wallpaper_sources,
+    wallpaper_tags,
     wallpapers,
 );
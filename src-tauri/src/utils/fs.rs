@@ -28,7 +28,6 @@ pub fn get_app_data_dir() -> PathBuf {
     app_data_dir
 }
 
-#[allow(dead_code)]
 pub fn get_config_dir() -> PathBuf {
     let config_dir = directories::BaseDirs::new()
         .expect("Failed to get base dir")
@@ -41,3 +40,11 @@ pub fn get_config_dir() -> PathBuf {
 
     config_dir
 }
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), directories::BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
@@ -0,0 +1,195 @@
+use crate::database::models::Wallpaper;
+use crate::database::settings::{
+    get_setting, HOOK_RESTORE, HOOK_ROTATION, HOOK_SCAN, HOOK_TIMEOUT, HOOK_WALLPAPER_SET,
+};
+use crate::ipc::events::ScanProgress;
+use diesel::SqliteConnection;
+use std::os::unix::process::CommandExt;
+use std::process::{self, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Hooks still running, the CLI waits for them before exiting.
+static RUNNING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Environment variables handed to a hook.
+pub type Env = Vec<(&'static str, String)>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// A wallpaper was set on a screen, from the GUI, the CLI or history
+    WallpaperSet,
+    /// A screen got its stored wallpaper back, at startup or when it was connected
+    Restore,
+    /// A scan finished or was cancelled
+    Scan,
+    /// A rotation moved a screen to its next wallpaper
+    Rotation,
+}
+
+impl Event {
+    fn setting(self) -> &'static str {
+        match self {
+            Self::WallpaperSet => HOOK_WALLPAPER_SET,
+            Self::Restore => HOOK_RESTORE,
+            Self::Scan => HOOK_SCAN,
+            Self::Rotation => HOOK_ROTATION,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::WallpaperSet => "wallpaper_set",
+            Self::Restore => "restore",
+            Self::Scan => "scan",
+            Self::Rotation => "rotation",
+        }
+    }
+}
+
+/// What a hook learns about the wallpaper of one screen.
+pub fn wallpaper_env(screen: &str, wallpaper: &Wallpaper, mode: &str) -> Env {
+    vec![
+        ("HYPR_NITROGEN_SCREEN", screen.to_string()),
+        ("HYPR_NITROGEN_WALLPAPER", wallpaper.path.clone()),
+        ("HYPR_NITROGEN_WALLPAPER_ID", wallpaper.id.clone()),
+        ("HYPR_NITROGEN_MODE", mode.to_string()),
+        (
+            "HYPR_NITROGEN_KEYWORDS",
+            wallpaper.keywords.clone().unwrap_or_default(),
+        ),
+    ]
+}
+
+/// What a hook learns about a finished scan, the source is empty when all of them were scanned.
+pub fn scan_env(progress: &ScanProgress) -> Env {
+    vec![
+        (
+            "HYPR_NITROGEN_SOURCE_ID",
+            progress.source_id.clone().unwrap_or_default(),
+        ),
+        ("HYPR_NITROGEN_SCAN_STAGE", progress.stage.to_string()),
+        ("HYPR_NITROGEN_SCAN_INSERTED", progress.inserted.to_string()),
+        ("HYPR_NITROGEN_SCAN_REMOVED", progress.removed.to_string()),
+        ("HYPR_NITROGEN_SCAN_ERRORS", progress.errors.to_string()),
+    ]
+}
+
+pub fn timeout(conn: &mut SqliteConnection) -> Duration {
+    let secs = match get_setting(conn, HOOK_TIMEOUT) {
+        Ok(Some(v)) => v.parse::<u64>().unwrap_or_else(|e| {
+            log::warn!("Invalid {HOOK_TIMEOUT} '{v}': {e}");
+            DEFAULT_TIMEOUT_SECS
+        }),
+        Ok(None) => DEFAULT_TIMEOUT_SECS,
+        Err(e) => {
+            log::warn!("Failed to get {HOOK_TIMEOUT}: {e}");
+            DEFAULT_TIMEOUT_SECS
+        }
+    };
+
+    Duration::from_secs(secs)
+}
+
+/// Runs the hook configured for `event`, if any, once per entry of `envs`.
+pub fn trigger(conn: &mut SqliteConnection, event: Event, envs: Vec<Env>) {
+    let command = match get_setting(conn, event.setting()) {
+        Ok(Some(v)) if !v.trim().is_empty() => v,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("Failed to get the {} hook: {e}", event.name());
+            return;
+        }
+    };
+
+    let timeout = timeout(conn);
+
+    for mut env in envs {
+        env.push(("HYPR_NITROGEN_EVENT", event.name().to_string()));
+        spawn(event.name(), &command, env, timeout);
+    }
+}
+
+/// Runs `command` with `sh -c` in its own process group in the background, killing the group
+/// after `timeout`. Its stderr is ours, failures are only logged since whatever triggered the
+/// hook already happened.
+pub fn spawn(name: &str, command: &str, env: Env, timeout: Duration) {
+    let child = process::Command::new("sh")
+        .args(["-c", command])
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .process_group(0)
+        .spawn();
+
+    let mut child = match child {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Failed to run the {name} hook '{command}': {e}");
+            return;
+        }
+    };
+
+    let name = name.to_string();
+    let command = command.to_string();
+
+    let handle = std::thread::spawn(move || {
+        let deadline = Instant::now() + timeout;
+
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if Instant::now() >= deadline => {
+                    log::warn!("The {name} hook '{command}' timed out after {timeout:?}");
+
+                    // The whole group, `sh` may have started more processes
+                    // SAFETY: killpg only sends a signal, the group is the one `sh` leads
+                    if unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) } != 0 {
+                        let e = std::io::Error::last_os_error();
+                        log::warn!("Failed to kill the {name} hook group: {e}");
+
+                        if let Err(e) = child.kill() {
+                            log::warn!("Failed to kill the {name} hook: {e}");
+                        }
+                    }
+
+                    let _ = child.wait();
+                    return;
+                }
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    log::warn!("Failed to wait for the {name} hook '{command}': {e}");
+                    return;
+                }
+            }
+        };
+
+        if !status.success() {
+            log::warn!("The {name} hook '{command}' failed with {status}");
+        }
+    });
+
+    match RUNNING.lock() {
+        Ok(mut running) => {
+            running.retain(|h| !h.is_finished());
+            running.push(handle);
+        }
+        Err(e) => log::warn!("Failed to track hook: {e}"),
+    }
+}
+
+/// Blocks until every running hook exited or timed out.
+pub fn wait() {
+    let handles = match RUNNING.lock() {
+        Ok(mut running) => std::mem::take(&mut *running),
+        Err(_) => return,
+    };
+
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
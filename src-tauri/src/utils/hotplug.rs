@@ -0,0 +1,137 @@
+use crate::backend::{self, Assignment};
+use crate::database::connection::DbPool;
+use crate::database::models::*;
+use crate::database::settings::{get_setting, FALLBACK_MODE, FALLBACK_WALLPAPER};
+use crate::error::Error;
+use crate::hyprpaper::{self, Event};
+use crate::ipc::events::{MonitorEvent, MONITORS_CHANGED};
+use crate::schema;
+use crate::utils::{hooks, span};
+use diesel::prelude::*;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// hyprpaper receives the same event, give it time to create the output first.
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+/// The stored `active` row for `screen`, or the configured fallback.
+fn wallpaper_for_screen(
+    conn: &mut SqliteConnection,
+    screen: &str,
+) -> Result<Option<(Wallpaper, String)>, Error> {
+    let (wallpaper_id, mode) = match schema::active::table
+        .find(screen)
+        .get_result::<Active>(conn)
+        .optional()
+    {
+        Ok(Some(v)) => (v.wallpaper_id, v.mode),
+        Ok(None) => match get_setting(conn, FALLBACK_WALLPAPER) {
+            Ok(Some(id)) => {
+                let mode =
+                    get_setting(conn, FALLBACK_MODE)?.unwrap_or(backend::Mode::Default.to_string());
+
+                (id, mode)
+            }
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        },
+        Err(e) => return Err(e.into()),
+    };
+
+    match schema::wallpapers::table
+        .find(wallpaper_id)
+        .get_result::<Wallpaper>(conn)
+        .optional()
+    {
+        Ok(v) => Ok(v.map(|w| (w, mode))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn apply(conn: &mut SqliteConnection, screen: &str) -> Result<(), Error> {
+    let Some((wallpaper, mode)) = wallpaper_for_screen(conn, screen)? else {
+        log::info!("No wallpaper stored for new screen '{screen}'");
+        return Ok(());
+    };
+
+    let backend = backend::get_backend(conn)?;
+    let h_mode = backend::Mode::from_string(&mode)?;
+
+    let assignments = match h_mode {
+        // The new monitor changes the layout, so the whole span is cut again
+        backend::Mode::Span => {
+            let mut screens = schema::active::table
+                .filter(schema::active::wallpaper_id.eq(&wallpaper.id))
+                .filter(schema::active::mode.eq(&mode))
+                .select(schema::active::screen)
+                .get_results::<String>(conn)?;
+
+            if !screens.iter().any(|s| s == screen) {
+                screens.push(screen.to_string());
+            }
+
+            span::assignments(&wallpaper.path, &wallpaper.signature, &screens)?
+        }
+        _ => vec![Assignment::from_wallpaper(
+            screen.to_string(),
+            &wallpaper,
+            h_mode,
+        )],
+    };
+
+    match backend.set_batch(&assignments) {
+        Ok(results) => {
+            for r in results {
+                r.result?;
+            }
+
+            let env = hooks::wallpaper_env(screen, &wallpaper, &mode);
+            hooks::trigger(conn, hooks::Event::Restore, vec![env]);
+
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Re-applies wallpapers when Hyprland reports a new monitor and tells the frontend
+/// about every change. Runs for the lifetime of the process.
+pub fn spawn_listener(app: AppHandle, pool: DbPool) {
+    std::thread::spawn(move || loop {
+        let result = hyprpaper::listen(|event| {
+            let (screen, connected) = match event {
+                Event::MonitorAdded(screen) => (screen, true),
+                Event::MonitorRemoved(screen) => (screen, false),
+            };
+
+            if connected {
+                std::thread::sleep(SETTLE_DELAY);
+
+                match pool.get() {
+                    Ok(mut conn) => match apply(&mut conn, &screen) {
+                        Ok(_) => log::info!("Wallpaper applied to new screen '{screen}'"),
+                        Err(e) => log::error!("Failed to apply wallpaper to '{screen}': {e}"),
+                    },
+                    Err(e) => log::error!("Failed to apply wallpaper to '{screen}': {e}"),
+                }
+            }
+
+            if let Err(e) = app.emit(MONITORS_CHANGED, MonitorEvent { screen, connected }) {
+                log::error!("Failed to emit '{MONITORS_CHANGED}': {e}");
+            }
+        });
+
+        match result {
+            Ok(_) => log::warn!("Hyprland event socket closed"),
+            Err(hyprpaper::Error::MissingEnv(name)) => {
+                log::warn!("'{name}' is not set, monitor hotplug disabled");
+                return;
+            }
+            Err(e) => log::error!("Hyprland event listener failed: {e}"),
+        }
+
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+}
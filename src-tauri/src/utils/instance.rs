@@ -0,0 +1,276 @@
+use crate::cli::{self, Cli};
+use crate::database::connection::DbPool;
+use crate::error::Error;
+use crate::ipc::events::{ACTIVE_CHANGED, WALLPAPERS_CHANGED};
+use crate::utils::restore;
+use crate::APP_NAME;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, TryLockError};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Label of the window in `tauri.conf.json`.
+const MAIN_WINDOW: &str = "main";
+
+/// Sent by a second invocation, one JSON line.
+#[derive(Serialize, Deserialize)]
+struct Request {
+    /// Without the program name, empty for a plain launch
+    args: Vec<String>,
+    /// Relative paths in `args` are relative to this
+    cwd: Option<PathBuf>,
+}
+
+/// What the forwarded invocation would have printed and its exit code.
+#[derive(Serialize, Deserialize, Default)]
+struct Reply {
+    stdout: String,
+    /// Printed on stderr by the invocation
+    error: Option<String>,
+    exit_code: i32,
+}
+
+impl Reply {
+    fn error(error: String, exit_code: i32) -> Self {
+        Self {
+            stdout: String::new(),
+            error: Some(error),
+            exit_code,
+        }
+    }
+}
+
+/// `$XDG_RUNTIME_DIR/hypr-nitrogen.sock`, `None` without a runtime dir.
+fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join(format!("{APP_NAME}.sock")))
+}
+
+/// Lock file of the process running rotations, next to the socket.
+fn rotation_lock_path() -> Option<PathBuf> {
+    socket_path().map(|path| path.with_extension("rotation.lock"))
+}
+
+/// Waits until no other GUI or daemon runs rotations, so that each one ticks once. The lock
+/// lasts as long as the returned file, the kernel drops it when the process exits and a waiting
+/// instance takes over. `None` when there is no runtime dir or the lock failed, rotations then
+/// run regardless.
+pub fn lock_rotations() -> Option<File> {
+    let Some(path) = rotation_lock_path() else {
+        log::warn!("'XDG_RUNTIME_DIR' is not set, rotations may run in several instances");
+        return None;
+    };
+
+    let file = match File::create(&path) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Failed to open '{}': {e}", path.to_string_lossy());
+            return None;
+        }
+    };
+
+    let result = match file.try_lock() {
+        Err(TryLockError::WouldBlock) => {
+            log::info!("Rotations run in another instance, waiting for it to exit");
+            file.lock()
+        }
+        Err(TryLockError::Error(e)) => Err(e),
+        Ok(_) => Ok(()),
+    };
+
+    match result {
+        Ok(_) => Some(file),
+        Err(e) => {
+            log::warn!("Failed to lock '{}': {e}", path.to_string_lossy());
+            None
+        }
+    }
+}
+
+fn exchange(stream: &mut UnixStream, request: &Request) -> std::io::Result<Reply> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+
+    stream.write_all(line.as_bytes())?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let mut text = String::new();
+    stream.read_to_string(&mut text)?;
+
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// Hands this invocation to the running GUI, which prints nothing itself, and returns the exit
+/// code. `None` when no GUI is running, the invocation then runs on its own.
+pub fn forward(json: bool) -> Option<i32> {
+    let mut stream = UnixStream::connect(socket_path()?).ok()?;
+
+    let request = Request {
+        args: std::env::args().skip(1).collect(),
+        cwd: std::env::current_dir().ok(),
+    };
+
+    log::debug!("forwarding {:?} to the running instance", request.args);
+
+    let reply = match exchange(&mut stream, &request) {
+        Ok(v) => v,
+        Err(e) => {
+            let error = Error::Io(e);
+            cli::print_error(&error, json);
+            return Some(error.exit_code());
+        }
+    };
+
+    print!("{}", reply.stdout);
+
+    // Already rendered by the instance, like `print_error` would
+    if let Some(e) = reply.error {
+        eprintln!("{e}");
+    }
+
+    Some(reply.exit_code)
+}
+
+fn show(app: &AppHandle) {
+    let Some(window) = app.get_webview_window(MAIN_WINDOW) else {
+        return;
+    };
+
+    if let Err(e) = window
+        .unminimize()
+        .and_then(|_| window.show())
+        .and_then(|_| window.set_focus())
+    {
+        log::warn!("Failed to show the window: {e}");
+    }
+}
+
+fn handle(app: &AppHandle, pool: &DbPool, request: Request) -> Reply {
+    let args = std::iter::once(APP_NAME.to_string()).chain(request.args);
+
+    let cli = match Cli::try_parse_from(args) {
+        Ok(v) => v,
+        // `--help` and `--version` end up here too, on stdout
+        Err(e) if e.use_stderr() => {
+            return Reply::error(e.render().to_string().trim_end().to_string(), e.exit_code())
+        }
+        Err(e) => {
+            return Reply {
+                stdout: e.render().to_string(),
+                error: None,
+                exit_code: e.exit_code(),
+            }
+        }
+    };
+
+    let result = if cli.restore {
+        pool.get()
+            .map_err(Error::from)
+            .and_then(|mut conn| restore(&mut conn))
+            .map(|_| String::new())
+    } else if let Some(mut command) = cli.command {
+        if let Some(cwd) = &request.cwd {
+            command.resolve_paths(cwd);
+        }
+
+        cli::render(command, pool, cli.json)
+    } else {
+        show(app);
+        return Reply::default();
+    };
+
+    for event in [ACTIVE_CHANGED, WALLPAPERS_CHANGED] {
+        if let Err(e) = app.emit(event, ()) {
+            log::error!("Failed to emit '{event}': {e}");
+        }
+    }
+
+    match result {
+        Ok(stdout) => Reply {
+            stdout,
+            ..Default::default()
+        },
+        Err(e) if cli.json => Reply {
+            stdout: cli::error_json(&e),
+            error: None,
+            exit_code: e.exit_code(),
+        },
+        Err(e) => Reply::error(e.to_string(), e.exit_code()),
+    }
+}
+
+fn serve(stream: UnixStream, app: &AppHandle, pool: &DbPool) {
+    let mut line = String::new();
+
+    if let Err(e) = BufReader::new(&stream).read_line(&mut line) {
+        log::warn!("Failed to read a forwarded invocation: {e}");
+        return;
+    }
+
+    let reply = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => handle(app, pool, request),
+        Err(e) => Reply::error(format!("Invalid request: {e}"), 2),
+    };
+
+    if let Err(e) = serde_json::to_writer(&stream, &reply) {
+        log::warn!("Failed to reply to a forwarded invocation: {e}");
+    }
+}
+
+/// Replaces a socket left behind by an instance that didn't exit cleanly.
+fn bind(path: &Path) -> std::io::Result<UnixListener> {
+    match UnixListener::bind(path) {
+        Err(e) if e.kind() == ErrorKind::AddrInUse && UnixStream::connect(path).is_err() => {
+            std::fs::remove_file(path)?;
+            UnixListener::bind(path)
+        }
+        result => result,
+    }
+}
+
+/// Makes this GUI the instance later invocations are forwarded to, see [`forward`]. Exits
+/// after forwarding to the other GUI when one took the socket first.
+pub fn spawn_listener(app: AppHandle, pool: DbPool) {
+    let Some(path) = socket_path() else {
+        log::warn!("'XDG_RUNTIME_DIR' is not set, other invocations won't reach this instance");
+        return;
+    };
+
+    let listener = match bind(&path) {
+        Ok(v) => v,
+        // Another GUI started between `forward` and now, `bind` keeps a live socket
+        Err(e) if e.kind() == ErrorKind::AddrInUse => match forward(false) {
+            Some(code) => std::process::exit(code),
+            None => {
+                log::error!(
+                    "Failed to reach the instance on '{}'",
+                    path.to_string_lossy()
+                );
+                return;
+            }
+        },
+        Err(e) => {
+            log::error!("Failed to listen on '{}': {e}", path.to_string_lossy());
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let app = app.clone();
+                    let pool = pool.clone();
+
+                    // A scan may take a while, other invocations don't wait for it
+                    std::thread::spawn(move || serve(stream, &app, &pool));
+                }
+                Err(e) => log::warn!("Failed to accept a forwarded invocation: {e}"),
+            }
+        }
+    });
+}
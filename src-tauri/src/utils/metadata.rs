@@ -0,0 +1,175 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// Version of the metadata file format, see the README. Files without a version are the
+/// bare JSON list `image_metadata.json` used to be.
+pub const VERSION: u32 = 1;
+
+/// Names of the metadata files read by full scans, the extension tells the format.
+pub const FILE_NAMES: [&str; 4] = [
+    "image_metadata.json",
+    "image_metadata.yaml",
+    "image_metadata.yml",
+    "image_metadata.toml",
+];
+
+/// Entry of an image, matched to its file by the blake3 hash of the content.
+#[derive(Deserialize, Clone, Debug)]
+pub struct WallpaperMetadata {
+    pub signature: String,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub category: String,
+    /// Space or comma separated
+    #[serde(default)]
+    pub tags: String,
+}
+
+impl WallpaperMetadata {
+    pub fn caption(&self) -> Option<String> {
+        non_empty(&self.caption)
+    }
+
+    pub fn category(&self) -> Option<String> {
+        non_empty(&self.category)
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[derive(Deserialize)]
+struct MetadataFile {
+    version: u32,
+    #[serde(default)]
+    images: Vec<WallpaperMetadata>,
+}
+
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+pub fn is_metadata_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| FILE_NAMES.contains(&n))
+}
+
+fn parse(text: &str, format: Format) -> Result<Vec<WallpaperMetadata>, String> {
+    let file: MetadataFile = match format {
+        // Unversioned files are a list, versioned ones an object
+        Format::Json if text.trim_start().starts_with('[') => {
+            return serde_json::from_str(text).map_err(|e| e.to_string());
+        }
+        Format::Json => serde_json::from_str(text).map_err(|e| e.to_string())?,
+        Format::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string())?,
+        Format::Toml => toml::from_str(text).map_err(|e| e.to_string())?,
+    };
+
+    if file.version > VERSION {
+        return Err(format!(
+            "version {} is newer than the supported version {VERSION}",
+            file.version
+        ));
+    }
+
+    Ok(file.images)
+}
+
+/// Reads the entries of a metadata file, errors start with its path.
+pub fn read(path: &Path) -> Result<Vec<WallpaperMetadata>, String> {
+    let format = match path.extension().and_then(|e| e.to_str()) {
+        Some("yaml" | "yml") => Format::Yaml,
+        Some("toml") => Format::Toml,
+        _ => Format::Json,
+    };
+
+    std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse(&text, format))
+        .map_err(|e| format!("{}: {e}", path.to_string_lossy()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signatures(entries: &[WallpaperMetadata]) -> Vec<&str> {
+        entries.iter().map(|e| e.signature.as_str()).collect()
+    }
+
+    #[test]
+    fn unversioned_json_list() {
+        let entries = parse(
+            r#"[{"signature": "a", "tags": "sea dusk"}, {"signature": "b"}]"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        assert_eq!(signatures(&entries), ["a", "b"]);
+        assert_eq!(entries[0].tags, "sea dusk");
+        assert_eq!(entries[1].caption(), None);
+    }
+
+    #[test]
+    fn versioned_json() {
+        let entries = parse(
+            r#"{"version": 1, "images": [{"signature": "a", "caption": " Dusk ", "category": " "}]}"#,
+            Format::Json,
+        )
+        .unwrap();
+
+        assert_eq!(entries[0].caption().as_deref(), Some("Dusk"));
+        assert_eq!(entries[0].category(), None);
+    }
+
+    #[test]
+    fn yaml() {
+        let text = "version: 1\nimages:\n  - signature: a\n    caption: Dusk\n    category: sea\n";
+        let entries = parse(text, Format::Yaml).unwrap();
+
+        assert_eq!(signatures(&entries), ["a"]);
+        assert_eq!(entries[0].category().as_deref(), Some("sea"));
+    }
+
+    #[test]
+    fn toml() {
+        let text = "version = 1\n\n[[images]]\nsignature = \"a\"\ntags = \"sea, dusk\"\n\n[[images]]\nsignature = \"b\"\n";
+        let entries = parse(text, Format::Toml).unwrap();
+
+        assert_eq!(signatures(&entries), ["a", "b"]);
+        assert_eq!(entries[0].tags, "sea, dusk");
+    }
+
+    #[test]
+    fn versioned_without_images() {
+        assert!(parse("version = 1", Format::Toml).unwrap().is_empty());
+    }
+
+    #[test]
+    fn missing_version() {
+        assert!(parse(r#"{"images": []}"#, Format::Json).is_err());
+        assert!(parse("images: []", Format::Yaml).is_err());
+        assert!(parse("images = []", Format::Toml).is_err());
+    }
+
+    #[test]
+    fn newer_version() {
+        let error = parse(r#"{"version": 2, "images": []}"#, Format::Json).unwrap_err();
+
+        assert!(error.contains("version 2 is newer"), "{error}");
+        assert!(parse("version: 2\nimages: []\n", Format::Yaml).is_err());
+        assert!(parse("version = 2", Format::Toml).is_err());
+    }
+
+    #[test]
+    fn metadata_file_names() {
+        assert!(is_metadata_file(Path::new("/w/image_metadata.yml")));
+        assert!(!is_metadata_file(Path::new("/w/metadata.json")));
+    }
+}
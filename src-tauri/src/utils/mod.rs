@@ -1,5 +1,25 @@
 pub mod fs;
+pub mod hooks;
+pub mod hotplug;
+pub mod instance;
+pub mod metadata;
+pub mod palette;
+pub mod phash;
+pub mod prescale;
 mod restore;
+pub mod rotation;
 pub mod scan;
+pub mod span;
+pub mod theme;
+mod thumbnail;
+pub mod watcher;
 
 pub use restore::restore;
+
+/// Current unix timestamp in seconds.
+pub fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
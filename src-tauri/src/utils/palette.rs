@@ -0,0 +1,146 @@
+use image::DynamicImage;
+
+/// Number of colors stored per wallpaper.
+pub const PALETTE_SIZE: usize = 8;
+
+/// Side of the copy the colors are sampled from, plenty for a palette.
+const SAMPLE_SIZE: u32 = 64;
+
+pub type Color = [u8; 3];
+
+pub fn to_hex([r, g, b]: Color) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn from_hex(string: &str) -> Option<Color> {
+    let hex = string.trim().strip_prefix('#')?;
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Stored form of a palette, see `wallpapers.palette`.
+pub fn to_string(colors: &[Color]) -> String {
+    colors
+        .iter()
+        .map(|c| to_hex(*c))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn parse(string: &str) -> Vec<Color> {
+    string.split(',').filter_map(from_hex).collect()
+}
+
+/// Relative luminance, 0 for black and 1 for white.
+pub fn luminance([r, g, b]: Color) -> f64 {
+    (0.2126 * f64::from(r) + 0.7152 * f64::from(g) + 0.0722 * f64::from(b)) / 255.0
+}
+
+/// HSV saturation times value, high for vivid colors only.
+pub fn vividness(color: Color) -> f64 {
+    let max = f64::from(*color.iter().max().unwrap_or(&0));
+    let min = f64::from(*color.iter().min().unwrap_or(&0));
+
+    (max - min) / 255.0
+}
+
+/// Moves `color` towards `target` by `amount`, between 0 and 1.
+pub fn mix(color: Color, target: Color, amount: f64) -> Color {
+    let channel = |i: usize| {
+        (f64::from(color[i]) + (f64::from(target[i]) - f64::from(color[i])) * amount).round() as u8
+    };
+
+    [channel(0), channel(1), channel(2)]
+}
+
+fn average(pixels: &[Color]) -> Color {
+    let mut sum = [0u64; 3];
+
+    for pixel in pixels {
+        for (s, c) in sum.iter_mut().zip(pixel) {
+            *s += u64::from(*c);
+        }
+    }
+
+    let len = pixels.len().max(1) as u64;
+    [
+        (sum[0] / len) as u8,
+        (sum[1] / len) as u8,
+        (sum[2] / len) as u8,
+    ]
+}
+
+/// Channel with the widest spread in `pixels` and that spread.
+fn widest_channel(pixels: &[Color]) -> (usize, u8) {
+    (0..3)
+        .map(|i| {
+            let min = pixels.iter().map(|p| p[i]).min().unwrap_or(0);
+            let max = pixels.iter().map(|p| p[i]).max().unwrap_or(0);
+            (i, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Median cut over a downsampled copy of `image`, most dominant color first. Images with
+/// few distinct colors get a shorter palette.
+pub fn extract(image: &DynamicImage) -> Vec<Color> {
+    let pixels: Vec<Color> = image
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .into_rgb8()
+        .pixels()
+        .map(|p| p.0)
+        .collect();
+
+    let mut boxes: Vec<Vec<Color>> = vec![pixels];
+
+    while boxes.len() < PALETTE_SIZE {
+        // Splitting the box with the widest spread, weighted by its size, keeps small but
+        // distinct areas (the accents) from being averaged away
+        let Some((index, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (
+                    i,
+                    channel,
+                    u64::from(range) * (b.len() as f64).sqrt() as u64,
+                )
+            })
+            .filter(|(_, _, score)| *score > 0)
+            .max_by_key(|(_, _, score)| *score)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+
+        let mut split = boxes.swap_remove(index);
+        split.sort_unstable_by_key(|p| p[channel]);
+
+        // Equal values stay on one side, otherwise a large flat area becomes several
+        // copies of the same color
+        let median = split[split.len() / 2][channel];
+        let mut at = split.partition_point(|p| p[channel] < median);
+
+        if at == 0 {
+            at = split.partition_point(|p| p[channel] <= median);
+        }
+
+        let upper = split.split_off(at);
+
+        boxes.push(split);
+        boxes.push(upper);
+    }
+
+    boxes.sort_by_key(|b| std::cmp::Reverse(b.len()));
+
+    boxes.iter().map(|b| average(b)).collect()
+}
@@ -0,0 +1,66 @@
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// Hamming distance up to which two hashes count as the same picture, resized or
+/// re-encoded copies usually stay well below it.
+pub const DEFAULT_MAX_DISTANCE: u32 = 10;
+
+/// 64 bit difference hash: the image is shrunk to 9x8 grayscale pixels and every bit
+/// tells whether a pixel is brighter than its right neighbour. Stored as i64 since
+/// SQLite has no unsigned integers.
+pub fn dhash(image: &DynamicImage) -> i64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).into_luma8();
+    let mut hash: u64 = 0;
+
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+
+            if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    hash as i64
+}
+
+pub fn distance(a: i64, b: i64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+
+    while parents[root] != root {
+        root = parents[root];
+    }
+
+    parents[i] = root;
+    root
+}
+
+/// Groups the indices of `hashes` whose distance to any other member is at most
+/// `max_distance`, singletons are left out.
+pub fn clusters(hashes: &[i64], max_distance: u32) -> Vec<Vec<usize>> {
+    let mut parents: Vec<usize> = (0..hashes.len()).collect();
+
+    for i in 0..hashes.len() {
+        for j in (i + 1)..hashes.len() {
+            if distance(hashes[i], hashes[j]) <= max_distance {
+                let a = find(&mut parents, i);
+                let b = find(&mut parents, j);
+                parents[b] = a;
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = vec![Vec::new(); hashes.len()];
+
+    for i in 0..hashes.len() {
+        let root = find(&mut parents, i);
+        groups[root].push(i);
+    }
+
+    groups.into_iter().filter(|g| g.len() > 1).collect()
+}
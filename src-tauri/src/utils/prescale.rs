@@ -0,0 +1,276 @@
+use crate::backend::{Background, Mode};
+use crate::hyprpaper::Monitor;
+use crate::utils::fs::get_cache_dir;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageError, ImageReader, Rgb, RgbImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const JPEG_QUALITY: u8 = 90;
+
+pub const DEFAULT_CACHE_SIZE_MB: u64 = 512;
+
+/// Each cache has its own directory and is evicted on its own.
+#[derive(Clone, Copy)]
+pub enum Cache {
+    /// Smaller copies for hyprpaper, see [`prescaled`]
+    Prescaled,
+    /// Modes the backend can't display, see [`rendered`]
+    Rendered,
+}
+
+impl Cache {
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Prescaled => "prescaled",
+            Self::Rendered => "rendered",
+        }
+    }
+}
+
+fn cache_dir(cache: Cache) -> PathBuf {
+    let mut path = get_cache_dir();
+    path.push(cache.dir_name());
+
+    if !path.exists() {
+        if let Err(e) = std::fs::create_dir(&path) {
+            log::error!("Failed to create {} dir: {e}", cache.dir_name());
+        }
+    }
+
+    path
+}
+
+/// One file per signature, monitor geometry and mode.
+fn cache_path(
+    cache: Cache,
+    signature: &str,
+    (width, height): (u32, u32),
+    scale: f64,
+    mode: &Mode,
+) -> PathBuf {
+    let mode = mode.to_string().replace([':', '#'], "-");
+
+    let mut path = cache_dir(cache);
+    path.push(format!("{signature}_{width}x{height}@{scale}_{mode}.jpeg"));
+    path
+}
+
+/// Draws `top` in the middle of `canvas`, cropping whatever doesn't fit.
+fn center_on(canvas: &mut RgbImage, top: &RgbImage) {
+    let x = (i64::from(canvas.width()) - i64::from(top.width())) / 2;
+    let y = (i64::from(canvas.height()) - i64::from(top.height())) / 2;
+
+    imageops::overlay(canvas, top, x, y);
+}
+
+/// Lays `image` out on a `width`x`height` screen the way `mode` describes. `Contain(None)`
+/// only scales, the borders are up to the backend.
+pub fn compose(image: &DynamicImage, (width, height): (u32, u32), mode: &Mode) -> RgbImage {
+    match mode {
+        Mode::Contain(None) => image
+            .resize(width, height, FilterType::CatmullRom)
+            .into_rgb8(),
+        Mode::Contain(Some(background)) => {
+            let mut canvas = match background {
+                Background::Color(color) => RgbImage::from_pixel(width, height, Rgb(*color)),
+                // Blurring a small copy is much cheaper and looks the same once scaled up
+                Background::Blur => image
+                    .resize_to_fill(
+                        (width / 16).max(1),
+                        (height / 16).max(1),
+                        FilterType::Triangle,
+                    )
+                    .blur(2.0)
+                    .resize_exact(width, height, FilterType::Triangle)
+                    .into_rgb8(),
+            };
+
+            center_on(
+                &mut canvas,
+                &image
+                    .resize(width, height, FilterType::CatmullRom)
+                    .into_rgb8(),
+            );
+            canvas
+        }
+        Mode::Stretch => image
+            .resize_exact(width, height, FilterType::CatmullRom)
+            .into_rgb8(),
+        Mode::Center => {
+            let mut canvas = RgbImage::new(width, height);
+            center_on(&mut canvas, &image.to_rgb8());
+            canvas
+        }
+        Mode::Tile => {
+            let mut canvas = RgbImage::new(width, height);
+            imageops::tile(&mut canvas, &image.to_rgb8());
+            canvas
+        }
+        Mode::Default | Mode::Cover | Mode::Span => image
+            .resize_to_fill(width, height, FilterType::CatmullRom)
+            .into_rgb8(),
+    }
+}
+
+/// Writes to a temporary file renamed into place once complete, so that an interrupted render
+/// never leaves a truncated file behind for later calls to take as a cache hit.
+fn render(src: &Path, dest: &Path, size: (u32, u32), mode: &Mode) -> Result<(), ImageError> {
+    let image = ImageReader::open(src)?.with_guessed_format()?.decode()?;
+    let composed = compose(&image, size, mode);
+
+    let partial = dest.with_extension("tmp");
+
+    let result = File::create(&partial)
+        .map_err(ImageError::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY).encode_image(&composed)?;
+            writer.flush()?;
+            Ok(())
+        })
+        .and_then(|_| Ok(std::fs::rename(&partial, dest)?));
+
+    if result.is_err() {
+        remove_partial(&partial);
+    }
+
+    result
+}
+
+fn remove_partial(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove '{}': {e}", path.to_string_lossy());
+        }
+    }
+}
+
+/// Marks a cache hit so eviction removes it last.
+fn touch(path: &Path) {
+    if let Err(e) = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|f| f.set_modified(SystemTime::now()))
+    {
+        log::warn!("Failed to touch '{}': {e}", path.to_string_lossy());
+    }
+}
+
+/// Returns a copy of `src` rendered at the pixel size of `monitor`, rendering it on a cache
+/// miss. `None` means the original should be used: tiled wallpapers keep their pixel size,
+/// images that aren't bigger than the monitor gain nothing, and rendering may fail.
+pub fn prescaled(src: &str, signature: &str, monitor: &Monitor, mode: &Mode) -> Option<PathBuf> {
+    if *mode == Mode::Tile {
+        return None;
+    }
+
+    let size = monitor.pixel_size();
+    let dest = cache_path(Cache::Prescaled, signature, size, monitor.scale, mode);
+
+    if dest.exists() {
+        touch(&dest);
+        return Some(dest);
+    }
+
+    match image::image_dimensions(src) {
+        Ok((w, h)) if u64::from(w) * u64::from(h) > u64::from(size.0) * u64::from(size.1) => {}
+        Ok(_) => return None,
+        Err(e) => {
+            log::warn!("Failed to read dimensions of '{src}': {e}");
+            return None;
+        }
+    }
+
+    match render(Path::new(src), &dest, size, mode) {
+        Ok(_) => {
+            log::debug!("prescaled '{src}' to '{}'", dest.to_string_lossy());
+            Some(dest)
+        }
+        Err(e) => {
+            log::warn!("Failed to prescale '{src}': {e}");
+            None
+        }
+    }
+}
+
+/// Returns `src` laid out on `monitor` by `mode`, for modes the backend can't display itself.
+/// Files outside of sources have no signature, their path and mtime are used instead.
+pub fn rendered(
+    src: &str,
+    signature: Option<&str>,
+    monitor: &Monitor,
+    mode: &Mode,
+) -> Result<PathBuf, ImageError> {
+    let signature = match signature {
+        Some(v) => v.to_string(),
+        None => {
+            let mtime = std::fs::metadata(src)?
+                .modified()?
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+
+            blake3::hash(format!("{src}:{mtime}").as_bytes()).to_hex()[..32].to_string()
+        }
+    };
+
+    let size = monitor.pixel_size();
+    let dest = cache_path(Cache::Rendered, &signature, size, monitor.scale, mode);
+
+    if dest.exists() {
+        touch(&dest);
+        return Ok(dest);
+    }
+
+    render(Path::new(src), &dest, size, mode)?;
+    log::debug!("rendered '{src}' as {mode} to '{}'", dest.to_string_lossy());
+
+    Ok(dest)
+}
+
+/// Removes the least recently used files until `cache` fits in `limit` bytes, `keep` are
+/// the files just handed to the backend.
+pub fn evict(cache: Cache, limit: u64, keep: &[PathBuf]) {
+    let entries = match std::fs::read_dir(cache_dir(cache)) {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Failed to read {} dir: {e}", cache.dir_name());
+            return;
+        }
+    };
+
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((e.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect();
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+
+    if total <= limit {
+        return;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    for (path, size, _) in files {
+        if total <= limit {
+            break;
+        }
+
+        if keep.contains(&path) {
+            continue;
+        }
+
+        match std::fs::remove_file(&path) {
+            Ok(_) => total -= size,
+            Err(e) => log::warn!("Failed to remove '{}': {e}", path.to_string_lossy()),
+        }
+    }
+}
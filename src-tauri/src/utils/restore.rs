@@ -1,16 +1,26 @@
+use crate::backend::{self, Assignment};
+use crate::database::history::{self, Origin};
 use crate::database::models::*;
-use crate::{hyprpaper, schema};
+use crate::error::Error;
+use crate::schema;
+use crate::utils::hooks::{self, Event};
+use crate::utils::{span, theme};
 use diesel::prelude::*;
+use std::collections::HashMap;
 
-pub fn restore(conn: &mut SqliteConnection) -> Result<bool, String> {
-    let active_wallpapers = match schema::active::table.get_results::<Active>(conn) {
-        Ok(v) => v,
-        Err(e) => return Err(e.to_string()),
-    };
+pub fn restore(conn: &mut SqliteConnection) -> Result<bool, Error> {
+    let backend = backend::get_backend(conn)?;
+
+    let active_wallpapers = schema::active::table.get_results::<Active>(conn)?;
+
+    let mut assignments: Vec<Assignment> = Vec::new();
+    let mut restored: HashMap<String, (Active, Wallpaper)> = HashMap::new();
+    let mut spans: HashMap<String, (Wallpaper, Vec<String>)> = HashMap::new();
+    let mut errors: Vec<String> = Vec::new();
 
     for active_wallpaper in active_wallpapers {
         let wallpaper = match schema::wallpapers::table
-            .filter(schema::wallpapers::dsl::id.eq(active_wallpaper.wallpaper_id))
+            .filter(schema::wallpapers::dsl::id.eq(&active_wallpaper.wallpaper_id))
             .get_result::<Wallpaper>(conn)
         {
             Ok(v) => v,
@@ -19,14 +29,82 @@ pub fn restore(conn: &mut SqliteConnection) -> Result<bool, String> {
             }
         };
 
-        if let Err(e) = hyprpaper::set_wallpaper(
-            active_wallpaper.screen,
-            wallpaper.path,
-            &hyprpaper::Mode::from_string(active_wallpaper.mode),
-        ) {
-            return Err(e.to_string());
+        let mode = match backend::Mode::from_string(&active_wallpaper.mode) {
+            Ok(v) => v,
+            Err(e) => {
+                errors.push(format!("{}: {e}", active_wallpaper.screen));
+                continue;
+            }
+        };
+
+        restored.insert(
+            active_wallpaper.screen.clone(),
+            (active_wallpaper.clone(), wallpaper.clone()),
+        );
+
+        match mode {
+            backend::Mode::Span => {
+                spans
+                    .entry(wallpaper.id.clone())
+                    .or_insert_with(|| (wallpaper, Vec::new()))
+                    .1
+                    .push(active_wallpaper.screen.clone());
+            }
+            _ => assignments.push(Assignment::from_wallpaper(
+                active_wallpaper.screen.clone(),
+                &wallpaper,
+                mode,
+            )),
+        }
+    }
+
+    // Spans are cut again for the current layout, every screen keeps its part
+    for (wallpaper, screens) in spans.into_values() {
+        match span::assignments(&wallpaper.path, &wallpaper.signature, &screens) {
+            Ok(v) => assignments.extend(v),
+            Err(e) => errors.extend(screens.into_iter().map(|s| format!("{s}: {e}"))),
         }
     }
 
+    let results = backend.set_batch(&assignments)?;
+    let mut themed: Option<String> = None;
+    let mut hook_envs: Vec<hooks::Env> = Vec::new();
+
+    for r in results {
+        match r.result {
+            Ok(_) => {
+                if let Some((active, wallpaper)) = restored.get(&r.screen) {
+                    themed.get_or_insert_with(|| active.wallpaper_id.clone());
+                    hook_envs.push(hooks::wallpaper_env(
+                        &active.screen,
+                        wallpaper,
+                        &active.mode,
+                    ));
+
+                    history::record(
+                        conn,
+                        &active.screen,
+                        &active.wallpaper_id,
+                        &active.mode,
+                        Origin::Restore,
+                    )?;
+                }
+            }
+            Err(e) => errors.push(format!("{}: {e}", r.screen)),
+        }
+    }
+
+    if let Some(id) = themed {
+        if let Err(e) = theme::export_by_id(conn, &id) {
+            log::warn!("Failed to export theme: {e}");
+        }
+    }
+
+    hooks::trigger(conn, Event::Restore, hook_envs);
+
+    if !errors.is_empty() {
+        return Err(Error::ScreensFailed(errors));
+    }
+
     Ok(true)
 }
@@ -0,0 +1,176 @@
+use crate::commands;
+use crate::database::connection::DbPool;
+use crate::database::history::Origin;
+use crate::database::models::*;
+use crate::error::Error;
+use crate::schema;
+use crate::utils::{instance, now};
+use diesel::prelude::*;
+use rand::Rng;
+use std::collections::HashSet;
+use std::time::Duration;
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Wallpapers `rotation` may pick from, sorted by path for sequential order, or in the order
+/// of its collection.
+fn candidates(conn: &mut SqliteConnection, rotation: &Rotation) -> Result<Vec<Wallpaper>, Error> {
+    if let Some(collection_id) = &rotation.collection_id {
+        return Ok(commands::get_collection_wallpapers(conn, collection_id)?
+            .into_iter()
+            .filter(|w| !rotation.favorites_only || w.is_favorite)
+            .filter(|w| {
+                rotation
+                    .wallpaper_source_id
+                    .as_ref()
+                    .is_none_or(|id| *id == w.wallpaper_source_id)
+            })
+            .collect());
+    }
+
+    let mut query = schema::wallpapers::table
+        .inner_join(schema::wallpaper_sources::table)
+        .filter(schema::wallpaper_sources::active.eq(true))
+        .select(schema::wallpapers::all_columns)
+        .into_boxed();
+
+    if let Some(source_id) = &rotation.wallpaper_source_id {
+        query = query.filter(schema::wallpapers::wallpaper_source_id.eq(source_id));
+    }
+
+    if rotation.favorites_only {
+        query = query.filter(schema::wallpapers::is_favorite.eq(true));
+    }
+
+    Ok(query
+        .order(schema::wallpapers::path.asc())
+        .get_results::<Wallpaper>(conn)?)
+}
+
+/// Picks the next wallpaper, starting a new cycle once every candidate was shown.
+fn next_wallpaper(
+    conn: &mut SqliteConnection,
+    rotation: &Rotation,
+) -> Result<Option<Wallpaper>, Error> {
+    let candidates = candidates(conn, rotation)?;
+
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let played: HashSet<String> = schema::rotation_played::table
+        .filter(schema::rotation_played::screen.eq(&rotation.screen))
+        .select(schema::rotation_played::wallpaper_id)
+        .get_results::<String>(conn)?
+        .into_iter()
+        .collect();
+
+    let mut remaining: Vec<&Wallpaper> = candidates
+        .iter()
+        .filter(|w| !played.contains(&w.id))
+        .collect();
+
+    if remaining.is_empty() {
+        diesel::delete(
+            schema::rotation_played::table
+                .filter(schema::rotation_played::screen.eq(&rotation.screen)),
+        )
+        .execute(conn)?;
+
+        remaining = candidates.iter().collect();
+    }
+
+    let picked = if rotation.shuffle {
+        let mut rng = rand::rng();
+        remaining[rng.random_range(0..remaining.len())]
+    } else {
+        remaining[0]
+    };
+
+    Ok(Some(picked.clone()))
+}
+
+/// Applies the next wallpaper of `rotation` right away.
+pub fn advance(
+    conn: &mut SqliteConnection,
+    rotation: &Rotation,
+) -> Result<Option<Wallpaper>, Error> {
+    let result = next_wallpaper(conn, rotation).and_then(|wallpaper| {
+        if let Some(w) = &wallpaper {
+            commands::apply_wallpaper(
+                conn,
+                &rotation.screen,
+                w,
+                rotation.mode.clone(),
+                false,
+                Origin::Rotation,
+            )?;
+
+            diesel::insert_into(schema::rotation_played::table)
+                .values((
+                    schema::rotation_played::screen.eq(&rotation.screen),
+                    schema::rotation_played::wallpaper_id.eq(&w.id),
+                ))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Ok(wallpaper)
+    });
+
+    // Failed attempts also count, otherwise a broken screen would be retried every tick
+    diesel::update(schema::rotations::table.find(&rotation.screen))
+        .set(schema::rotations::last_changed_at.eq(now()))
+        .execute(conn)?;
+
+    result
+}
+
+/// Advances every enabled rotation whose interval elapsed, returns how many changed.
+pub fn tick(conn: &mut SqliteConnection) -> Result<usize, Error> {
+    let now = now();
+    let mut changed = 0;
+
+    let rotations = schema::rotations::table
+        .filter(schema::rotations::enabled.eq(true))
+        .get_results::<Rotation>(conn)?;
+
+    for rotation in rotations
+        .iter()
+        .filter(|r| now - r.last_changed_at >= i64::from(r.interval_secs))
+    {
+        match advance(conn, rotation) {
+            Ok(Some(w)) => {
+                log::info!("Rotated '{}' to '{}'", rotation.screen, w.path);
+                changed += 1;
+            }
+            Ok(None) => log::warn!("Rotation for '{}' has no wallpapers", rotation.screen),
+            Err(e) => log::error!("Rotation for '{}' failed: {e}", rotation.screen),
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Runs the rotation loop forever, calling `on_change` after wallpapers changed. Waits for
+/// other instances running rotations first, see [`instance::lock_rotations`].
+pub fn run<F: FnMut()>(pool: DbPool, mut on_change: F) -> ! {
+    let _lock = instance::lock_rotations();
+
+    loop {
+        match pool.get() {
+            Ok(mut conn) => match tick(&mut conn) {
+                Ok(0) => {}
+                Ok(_) => on_change(),
+                Err(e) => log::error!("Rotation tick failed: {e}"),
+            },
+            Err(e) => log::error!("Rotation tick failed: {e}"),
+        }
+
+        std::thread::sleep(TICK_INTERVAL);
+    }
+}
+
+pub fn spawn<F: FnMut() + Send + 'static>(pool: DbPool, on_change: F) {
+    std::thread::spawn(move || run(pool, on_change));
+}
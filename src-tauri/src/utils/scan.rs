@@ -1,47 +1,167 @@
+use crate::config;
 use crate::database::models::*;
-use crate::ipc::Response;
+use crate::database::tags::{self, Origin};
+use crate::error::Error;
+use crate::ipc::events::{ScanProgress, ScanStage};
 use crate::schema;
 use crate::utils::fs::get_cache_dir;
+use crate::utils::metadata::{self, WallpaperMetadata};
+use crate::utils::thumbnail;
 use diesel::prelude::*;
 use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use futures::StreamExt;
-use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tauri::async_runtime;
 use walkdir::WalkDir;
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct WallpaperMetadata {
-    pub signature: String,
-    pub caption: String,
-    pub category: String,
-    pub tags: String,
-}
-
 type MetadataHashMap = HashMap<String, WallpaperMetadata>;
 type WallpapersHashMap = HashMap<String, NewWallpaper>;
 type ImageSource = PathBuf;
 type ThumbnailDest = PathBuf;
-type ThumbnailTask = (ImageSource, ThumbnailDest);
+/// Id of the row whose perceptual hash or palette is missing
+type AnalysisTarget = Option<String>;
+type ThumbnailTask = (ImageSource, ThumbnailDest, AnalysisTarget);
+/// Known copies by path, with the path of the wallpaper they copy
+type DuplicatesHashMap = HashMap<String, (Duplicate, String)>;
 
-static IMAGE_EXTENSIONS_ARRAY: &[&str] = &["jpg", "jpeg", "png", "gif", "webp"];
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
 
-fn extract_metadata(metadata: &mut MetadataHashMap, path: &Path) {
-    match std::fs::read_to_string(path) {
-        Ok(t) => match serde_json::from_str::<Vec<WallpaperMetadata>>(&t) {
-            Ok(d) => {
-                for item in d {
-                    metadata.insert(item.signature.clone(), item.clone());
-                }
+#[derive(Default)]
+struct ScanFlags {
+    cancel: AtomicBool,
+    running: AtomicUsize,
+}
+
+/// Shared between running scans and `cmd_cancel_scan`, managed by tauri.
+#[derive(Default, Clone)]
+pub struct ScanControl(Arc<ScanFlags>);
+
+impl ScanControl {
+    /// Stops every running scan after the file or thumbnail at hand. Returns false when
+    /// nothing was running.
+    pub fn cancel(&self) -> bool {
+        if self.0.running.load(Ordering::SeqCst) == 0 {
+            return false;
+        }
+
+        self.0.cancel.store(true, Ordering::SeqCst);
+        true
+    }
+}
+
+/// Progress reporting and cancellation of one scan run, see [`ScanProgress`].
+pub struct ScanContext {
+    flags: Arc<ScanFlags>,
+    progress: ScanProgress,
+    on_progress: Box<dyn FnMut(&ScanProgress) + Send>,
+    last_report: Instant,
+}
+
+impl ScanContext {
+    pub fn new(
+        control: &ScanControl,
+        on_progress: impl FnMut(&ScanProgress) + Send + 'static,
+    ) -> Self {
+        // A cancel that arrived after the last scan ended must not stop this one
+        if control.0.running.fetch_add(1, Ordering::SeqCst) == 0 {
+            control.0.cancel.store(false, Ordering::SeqCst);
+        }
+
+        Self {
+            flags: control.0.clone(),
+            progress: ScanProgress::default(),
+            on_progress: Box::new(on_progress),
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flags.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Updates the counters, reporting them when the last report is old enough.
+    fn update(&mut self, f: impl FnOnce(&mut ScanProgress)) {
+        f(&mut self.progress);
+
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.report();
+        }
+    }
+
+    fn error(&mut self, message: String) {
+        self.update(|p| {
+            p.errors += 1;
+            p.last_error = Some(message);
+        });
+    }
+
+    fn set_stage(&mut self, stage: ScanStage) {
+        self.progress.stage = stage;
+        self.report();
+    }
+
+    fn report(&mut self) {
+        (self.on_progress)(&self.progress);
+        self.last_report = Instant::now();
+    }
+
+    /// Counters of the run so far.
+    pub fn progress(&self) -> &ScanProgress {
+        &self.progress
+    }
+
+    /// Reports the final counters, as `cancelled` when the run was cut short.
+    pub fn finish(&mut self) {
+        match self.is_cancelled() {
+            true => self.set_stage(ScanStage::Cancelled),
+            false => self.set_stage(ScanStage::Done),
+        }
+    }
+
+    /// Reports the error that stopped the run as its final counters.
+    pub fn fail(&mut self, message: String) {
+        self.progress.errors += 1;
+        self.progress.last_error = Some(message);
+        self.set_stage(ScanStage::Failed);
+    }
+}
+
+impl Drop for ScanContext {
+    fn drop(&mut self) {
+        // A run that returned early without `finish` or `fail` still ends with a final report
+        if matches!(
+            self.progress.stage,
+            ScanStage::Walking | ScanStage::Thumbnails
+        ) {
+            match self.is_cancelled() {
+                true => self.set_stage(ScanStage::Cancelled),
+                false => self.set_stage(ScanStage::Failed),
             }
-            Err(e) => {
-                log::error!("Error reading metadata file: {e}");
+        }
+
+        self.flags.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Returns whether the file could be read.
+fn extract_metadata(metadata: &mut MetadataHashMap, path: &Path, ctx: &mut ScanContext) -> bool {
+    match metadata::read(path) {
+        Ok(d) => {
+            for item in d {
+                metadata.insert(item.signature.clone(), item);
             }
-        },
+
+            true
+        }
         Err(e) => {
             log::error!("Error reading metadata file: {e}");
+            ctx.error(format!("Error reading metadata file: {e}"));
+            false
         }
     }
 }
@@ -49,15 +169,16 @@ fn extract_metadata(metadata: &mut MetadataHashMap, path: &Path) {
 fn is_image_extension(ext_os_str: &OsStr) -> bool {
     ext_os_str.to_str().is_some_and(|s| {
         let lowercased_s = s.to_lowercase(); // Creates a temporary String
-        IMAGE_EXTENSIONS_ARRAY.contains(&lowercased_s.as_str()) // Linear search
+        config::get().scan.extensions.contains(&lowercased_s) // Linear search
     })
 }
 
 fn generate_signature(path: &Path) -> Option<String> {
-    match std::fs::read(path) {
-        Ok(bytes) => {
-            let hash = blake3::hash(&bytes);
-
+    // Streams the file, large images don't have to fit in memory
+    match std::fs::File::open(path)
+        .and_then(|f| blake3::Hasher::new().update_reader(f).map(|h| h.finalize()))
+    {
+        Ok(hash) => {
             return Some(hash.to_string());
         }
         Err(e) => {
@@ -68,6 +189,91 @@ fn generate_signature(path: &Path) -> Option<String> {
     None
 }
 
+/// Reads width and height from the image header without decoding it.
+fn read_dimensions(path: &Path) -> Option<(u32, u32)> {
+    match image::image_dimensions(path) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            log::warn!(
+                "Failed to read dimensions of '{}': {e}",
+                path.to_string_lossy()
+            );
+            None
+        }
+    }
+}
+
+/// Size and mtime, compared against the stored values to skip unchanged files.
+fn file_stamp(path: &Path) -> Option<(i64, i64)> {
+    match std::fs::metadata(path) {
+        Ok(m) => {
+            let mtime = m
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)?;
+
+            Some((m.len() as i64, mtime))
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to read metadata of '{}': {e}",
+                path.to_string_lossy()
+            );
+            None
+        }
+    }
+}
+
+fn set_image_info(
+    wallpaper: &mut NewWallpaper,
+    dimensions: Option<(u32, u32)>,
+    stamp: Option<(i64, i64)>,
+) {
+    if let Some((width, height)) = dimensions {
+        wallpaper.width = Some(width as i32);
+        wallpaper.height = Some(height as i32);
+
+        if height > 0 {
+            wallpaper.aspect_ratio = Some(f64::from(width) / f64::from(height));
+        }
+    }
+
+    if let Some((file_size, mtime)) = stamp {
+        wallpaper.file_size = Some(file_size);
+        wallpaper.mtime = Some(mtime);
+    }
+}
+
+/// Points an existing row at a new or changed file, keeping its id, favorite and keywords.
+/// The perceptual hash and palette are cleared until the thumbnail pass computes them again.
+fn update_file(conn: &mut SqliteConnection, id: &str, file: &NewWallpaper) -> QueryResult<usize> {
+    diesel::update(schema::wallpapers::table.find(id))
+        .set((
+            schema::wallpapers::signature.eq(&file.signature),
+            schema::wallpapers::path.eq(&file.path),
+            schema::wallpapers::thumbnail_path.eq(&file.thumbnail_path),
+            schema::wallpapers::resolution.eq(&file.resolution),
+            schema::wallpapers::wallpaper_source_id.eq(&file.wallpaper_source_id),
+            schema::wallpapers::width.eq(file.width),
+            schema::wallpapers::height.eq(file.height),
+            schema::wallpapers::aspect_ratio.eq(file.aspect_ratio),
+            schema::wallpapers::file_size.eq(file.file_size),
+            schema::wallpapers::mtime.eq(file.mtime),
+            schema::wallpapers::phash.eq(file.phash),
+            schema::wallpapers::palette.eq(&file.palette),
+        ))
+        .execute(conn)
+}
+
+fn remove_thumbnail(thumbnail_path: &str) {
+    if let Err(e) = std::fs::remove_file(thumbnail_path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove thumbnail '{thumbnail_path}': {e}");
+        }
+    }
+}
+
 fn keywords_from_file_name(file_name: &OsStr) -> String {
     let string = file_name.to_string_lossy();
 
@@ -88,115 +294,294 @@ fn create_thumbnail_path(signature: &str) -> String {
     thumbnail_path.to_string_lossy().to_string()
 }
 
-async fn process_thumbnail_task_list(list: Vec<ThumbnailTask>) {
+/// Creates missing thumbnails, perceptual hashes and palettes, decoding each image only once.
+async fn process_thumbnail_task_list(
+    conn: &mut SqliteConnection,
+    list: Vec<ThumbnailTask>,
+    ctx: &mut ScanContext,
+) {
     let total_threads = std::thread::available_parallelism()
         .map(|x| x.get())
         .unwrap_or(4);
 
-    let stream = futures::stream::iter(list.into_iter().map(|(src, dest)| {
+    ctx.update(|p| p.thumbnails_total += list.len() as u32);
+
+    // Tasks are only spawned as the buffer drains, stopping the loop leaves the rest queued
+    let mut stream = futures::stream::iter(list.into_iter().map(|(src, dest, target)| {
         async_runtime::spawn_blocking(move || {
-            if !dest.exists() {
-                match std::process::Command::new("magick")
-                    .arg(src.as_os_str())
-                    .arg("-thumbnail")
-                    .arg("400x200^")
-                    .arg("-format")
-                    .arg("jpeg")
-                    .arg(dest.as_os_str())
-                    .output()
-                {
-                    Ok(cmd) => {
-                        if cmd.status.success() {
-                            log::debug!("thumbnail generated: {}", dest.to_string_lossy())
-                        } else {
-                            log::warn!("Failed to generate thumbnail: {}", src.to_string_lossy());
-                        }
-                    }
-                    Err(e) => log::warn!("Failed to run magick command: {e}"),
-                }
+            let dest = (!dest.exists()).then_some(dest);
+            let processed = thumbnail::process(&src, dest.as_deref(), target.is_some());
+
+            if !processed.thumbnail {
+                return Err(format!(
+                    "Failed to create thumbnail for '{}'",
+                    src.to_string_lossy()
+                ));
             }
+
+            Ok(target.zip(processed.phash).zip(processed.palette))
         })
     }))
     .buffer_unordered(total_threads);
 
-    stream
-        .for_each(|result| async {
-            if let Err(e) = result {
+    while let Some(result) = stream.next().await {
+        match result {
+            Ok(Ok(analysis)) => {
+                if let Some(((id, phash), palette)) = analysis {
+                    if let Err(e) = diesel::update(schema::wallpapers::table.find(&id))
+                        .set((
+                            schema::wallpapers::phash.eq(phash),
+                            schema::wallpapers::palette.eq(palette),
+                        ))
+                        .execute(conn)
+                    {
+                        log::error!("Failed to store perceptual hash and palette of '{id}': {e}");
+                    }
+                }
+
+                ctx.update(|p| p.thumbnails_done += 1);
+            }
+            Ok(Err(e)) => ctx.error(e),
+            Err(e) => {
                 log::error!("Thread paniced: {e}");
+                ctx.error(format!("Thread paniced: {e}"));
             }
-        })
-        .await;
+        }
+
+        if ctx.is_cancelled() {
+            break;
+        }
+    }
 }
 
-pub async fn scan(
+/// Records `file` as a copy of `wallpaper_id`, or refreshes its size and mtime.
+fn record_duplicate(conn: &mut SqliteConnection, duplicate: &NewDuplicate) -> QueryResult<usize> {
+    diesel::insert_into(schema::duplicates::table)
+        .values(duplicate)
+        .on_conflict(schema::duplicates::path)
+        .do_update()
+        .set(duplicate)
+        .execute(conn)
+}
+
+fn remove_duplicates(conn: &mut SqliteConnection, paths: &[&String]) -> QueryResult<usize> {
+    if paths.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::delete(schema::duplicates::table.filter(schema::duplicates::path.eq_any(paths)))
+        .execute(conn)
+}
+
+/// Outcome of [`sync_file`].
+enum FileSync {
+    /// Same size and mtime as the stored row
+    Unchanged,
+    /// An existing row now points at the file, `moved_from` is set when it was found by content
+    Updated { moved_from: Option<String> },
+    /// Returned instead of inserted so keywords can be filled in first
+    New(Box<NewWallpaper>),
+    /// Failed to hash
+    Unreadable,
+    /// A copy of a wallpaper that's still on disk, recorded in `duplicates`
+    Duplicate,
+    /// Changed into a copy of another wallpaper while keeping its own row
+    Skipped,
+}
+
+/// Compares one image file against its stored row, or its `duplicates` row, hashing it
+/// only when it changed, and queues its thumbnail and perceptual hash when needed.
+fn sync_file(
     conn: &mut SqliteConnection,
-    source_id: String,
-    source_path: String,
-) -> Result<Vec<Wallpaper>, String> {
-    let mut wallpapers_hashmap: WallpapersHashMap = HashMap::new();
-    let mut metadata: MetadataHashMap = HashMap::new();
-    let mut thumbnail_generation_list: Vec<ThumbnailTask> = Vec::new();
+    source_id: &str,
+    file: &Path,
+    existing: Option<&Wallpaper>,
+    known_copy: Option<&(Duplicate, String)>,
+    thumbnails: &mut Vec<ThumbnailTask>,
+) -> Result<FileSync, Error> {
+    let path = file.to_string_lossy().to_string();
+    let stamp = file_stamp(file);
 
-    for entry in WalkDir::new(source_path)
-        .into_iter()
-        .filter_map(|e| e.ok().filter(|x| x.path().is_file()))
-    {
-        if entry.file_name().to_string_lossy() == "image_metadata.json" {
-            extract_metadata(&mut metadata, entry.path());
-            continue;
+    if let Some(existing) = existing {
+        if stamp.is_some() && stamp == existing.file_size.zip(existing.mtime) {
+            // The cache may have been cleared since the last scan, and rows scanned
+            // before perceptual hashes and palettes were added still need them
+            let target = (existing.phash.is_none() || existing.palette.is_none())
+                .then(|| existing.id.clone());
+
+            if !Path::new(&existing.thumbnail_path).exists() || target.is_some() {
+                thumbnails.push((
+                    PathBuf::from(file),
+                    PathBuf::from(&existing.thumbnail_path),
+                    target,
+                ));
+            }
+
+            return Ok(FileSync::Unchanged);
+        }
+    }
+
+    // A copy stops being one once the wallpaper it copies is gone
+    if let Some((copy, original_path)) = known_copy {
+        if stamp.is_some()
+            && stamp == copy.file_size.zip(copy.mtime)
+            && Path::new(original_path).exists()
+        {
+            return Ok(FileSync::Duplicate);
         }
+    }
 
-        match entry.path().extension() {
-            Some(ext) => {
-                if is_image_extension(ext) {
-                    if let Some(signature) = generate_signature(entry.path()) {
-                        let thumbnail_path: String = create_thumbnail_path(&signature);
+    let Some(signature) = generate_signature(file) else {
+        return Ok(FileSync::Unreadable);
+    };
 
-                        thumbnail_generation_list
-                            .push((PathBuf::from(entry.path()), PathBuf::from(&thumbnail_path)));
+    let thumbnail_path: String = create_thumbnail_path(&signature);
+    let dimensions = read_dimensions(file);
 
-                        let new_wallpaper = NewWallpaper::new(
-                            signature.clone(),
-                            entry.path().to_string_lossy().to_string(),
-                            thumbnail_path,
-                            None, // TODO: Extract resolution
-                            source_id.clone(),
-                            None,
-                        );
+    let mut new_wallpaper = NewWallpaper::new(
+        signature.clone(),
+        path.clone(),
+        thumbnail_path.clone(),
+        dimensions.map(|(w, h)| format!("{w}x{h}")),
+        source_id.to_string(),
+        None,
+    );
 
-                        wallpapers_hashmap.insert(signature, new_wallpaper);
-                    }
+    set_image_info(&mut new_wallpaper, dimensions, stamp);
+
+    let (target, moved_from) = match existing {
+        Some(v) => (v.clone(), None),
+        None => {
+            let same_content = schema::wallpapers::table
+                .filter(schema::wallpapers::signature.eq(&signature))
+                .get_result::<Wallpaper>(conn)
+                .optional()?;
+
+            match same_content {
+                Some(v) if !Path::new(&v.path).exists() => {
+                    log::debug!("'{}' moved to '{path}'", v.path);
+                    let moved_from = Some(v.path.clone());
+                    (v, moved_from)
+                }
+                Some(v) => {
+                    let (file_size, mtime) = stamp.unzip();
+
+                    record_duplicate(
+                        conn,
+                        &NewDuplicate::new(path, v.id, source_id.to_string(), file_size, mtime),
+                    )?;
+
+                    return Ok(FileSync::Duplicate);
+                }
+                None => {
+                    thumbnails.push((
+                        PathBuf::from(file),
+                        PathBuf::from(&thumbnail_path),
+                        Some(new_wallpaper.id.clone()),
+                    ));
+                    return Ok(FileSync::New(Box::new(new_wallpaper)));
                 }
             }
-            None => {
-                continue;
+        }
+    };
+
+    match update_file(conn, &target.id, &new_wallpaper) {
+        Ok(_) => {
+            if target.signature != signature {
+                remove_thumbnail(&target.thumbnail_path);
             }
+
+            // The file may have been a copy of the wallpaper that moved onto it
+            remove_duplicates(conn, &[&path])?;
+        }
+        Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+            log::warn!("'{path}' now duplicates another wallpaper, skipping");
+            return Ok(FileSync::Skipped);
         }
+        Err(e) => return Err(e.into()),
     }
 
-    wallpapers_hashmap.iter_mut().for_each(|w| {
-        if let Some(metadata) = metadata.get(&w.1.signature) {
-            w.1.keywords = Some(metadata.tags.clone());
-        } else {
-            let mut temp_string = String::new();
+    thumbnails.push((
+        PathBuf::from(file),
+        PathBuf::from(&thumbnail_path),
+        Some(target.id),
+    ));
 
-            if let Some(file_name) = Path::new(&w.1.path).file_stem() {
-                temp_string.push_str(&keywords_from_file_name(file_name));
-            }
+    Ok(FileSync::Updated { moved_from })
+}
 
-            w.1.keywords = Some(temp_string);
+/// Keywords, caption and category from the metadata entry of the file, or keywords from
+/// its name when there is none.
+fn set_keywords(wallpaper: &mut NewWallpaper, metadata: &MetadataHashMap) {
+    if let Some(metadata) = metadata.get(&wallpaper.signature) {
+        wallpaper.keywords = Some(metadata.tags.clone());
+        wallpaper.caption = metadata.caption();
+        wallpaper.category = metadata.category();
+    } else {
+        let mut temp_string = String::new();
+
+        if let Some(file_name) = Path::new(&wallpaper.path).file_stem() {
+            temp_string.push_str(&keywords_from_file_name(file_name));
         }
-    });
 
-    let mut wallpapers_list: Vec<Wallpaper> = Vec::new();
+        wallpaper.keywords = Some(temp_string);
+    }
+}
 
-    for w in wallpapers_hashmap.values() {
+/// Queues a new file for [`insert_new`], or as a copy of a queued file with the same content.
+fn queue_new(
+    wallpapers: &mut WallpapersHashMap,
+    copies: &mut Vec<NewDuplicate>,
+    new_wallpaper: NewWallpaper,
+) {
+    match wallpapers.get(&new_wallpaper.signature) {
+        Some(first) => copies.push(NewDuplicate::new(
+            new_wallpaper.path,
+            first.id.clone(),
+            new_wallpaper.wallpaper_source_id,
+            new_wallpaper.file_size,
+            new_wallpaper.mtime,
+        )),
+        None => {
+            wallpapers.insert(new_wallpaper.signature.clone(), new_wallpaper);
+        }
+    }
+}
+
+/// Records the copies queued by [`queue_new`], once the rows they copy are inserted.
+fn record_copies(conn: &mut SqliteConnection, copies: &[NewDuplicate]) {
+    for copy in copies {
+        // Fails when another file claimed the signature first, the next scan records it
+        if let Err(e) = record_duplicate(conn, copy) {
+            log::warn!("Failed to record '{}' as a duplicate: {e}", copy.path);
+        }
+    }
+}
+
+/// Inserts new rows and their tags, skipping signatures another file claimed in the meantime.
+fn insert_new(
+    conn: &mut SqliteConnection,
+    wallpapers: &WallpapersHashMap,
+    metadata: &MetadataHashMap,
+) -> Result<usize, Error> {
+    let mut inserted = 0;
+
+    for w in wallpapers.values() {
         match diesel::insert_into(schema::wallpapers::table)
             .values(w)
-            .get_result::<Wallpaper>(conn)
+            .execute(conn)
         {
-            Ok(v) => {
-                wallpapers_list.push(v);
+            Ok(_) => {
+                inserted += 1;
+
+                let origin = if metadata.contains_key(&w.signature) {
+                    Origin::Metadata
+                } else {
+                    Origin::Filename
+                };
+                let names = tags::from_keywords(w.keywords.as_deref().unwrap_or_default());
+
+                tags::attach(conn, &w.id, &names, origin)?;
             }
             Err(DieselError::DatabaseError(kind, _)) => {
                 if let DatabaseErrorKind::UniqueViolation = kind {
@@ -204,34 +589,403 @@ pub async fn scan(
                 }
             }
             Err(e) => {
-                return Err(e.to_string());
+                return Err(e.into());
             }
         }
     }
 
-    if let Err(e) = std::process::Command::new("magick").arg("--help").output() {
-        log::error!("Failed to find magick command: {e}");
-        return Err(format!("Failed to find magick command: {e}"));
+    Ok(inserted)
+}
+
+/// Copies caption and category of the metadata entries onto the known rows of the source.
+/// With `complete`, rows without an entry lose theirs, the entry or its file was removed.
+/// Tags are left alone, they may have been edited.
+fn update_descriptions(
+    conn: &mut SqliteConnection,
+    source_id: &str,
+    metadata: &MetadataHashMap,
+    complete: bool,
+) -> Result<(), Error> {
+    if complete {
+        let stale: Vec<String> = schema::wallpapers::table
+            .filter(schema::wallpapers::wallpaper_source_id.eq(source_id))
+            .filter(
+                schema::wallpapers::caption
+                    .is_not_null()
+                    .or(schema::wallpapers::category.is_not_null()),
+            )
+            .select((schema::wallpapers::id, schema::wallpapers::signature))
+            .get_results::<(String, String)>(conn)?
+            .into_iter()
+            .filter(|(_, signature)| !metadata.contains_key(signature))
+            .map(|(id, _)| id)
+            .collect();
+
+        diesel::update(schema::wallpapers::table.filter(schema::wallpapers::id.eq_any(&stale)))
+            .set((
+                schema::wallpapers::caption.eq(None::<String>),
+                schema::wallpapers::category.eq(None::<String>),
+            ))
+            .execute(conn)?;
     }
 
-    process_thumbnail_task_list(thumbnail_generation_list).await;
+    for m in metadata.values() {
+        let (caption, category) = (m.caption(), m.category());
 
-    Ok(wallpapers_list)
+        diesel::update(
+            schema::wallpapers::table
+                .filter(schema::wallpapers::wallpaper_source_id.eq(source_id))
+                .filter(schema::wallpapers::signature.eq(&m.signature))
+                // Unchanged rows aren't written, the search index is rebuilt on every write
+                .filter(
+                    schema::wallpapers::caption
+                        .is_not(&caption)
+                        .or(schema::wallpapers::category.is_not(&category)),
+                ),
+        )
+        .set((
+            schema::wallpapers::caption.eq(&caption),
+            schema::wallpapers::category.eq(&category),
+        ))
+        .execute(conn)?;
+    }
+
+    Ok(())
+}
+
+/// Points the row of a deleted file at one of its copies, if any is still on disk.
+fn promote_copy(conn: &mut SqliteConnection, wallpaper: &Wallpaper) -> Result<bool, Error> {
+    let copies = schema::duplicates::table
+        .filter(schema::duplicates::wallpaper_id.eq(&wallpaper.id))
+        .get_results::<Duplicate>(conn)?;
+
+    let Some(copy) = copies.into_iter().find(|c| Path::new(&c.path).exists()) else {
+        return Ok(false);
+    };
+
+    diesel::update(schema::wallpapers::table.find(&wallpaper.id))
+        .set((
+            schema::wallpapers::path.eq(&copy.path),
+            schema::wallpapers::wallpaper_source_id.eq(&copy.wallpaper_source_id),
+            schema::wallpapers::file_size.eq(copy.file_size),
+            schema::wallpapers::mtime.eq(copy.mtime),
+        ))
+        .execute(conn)?;
+
+    remove_duplicates(conn, &[&copy.path])?;
+
+    log::debug!("'{}' replaced by its copy '{}'", wallpaper.path, copy.path);
+
+    Ok(true)
+}
+
+/// Removes the rows of deleted files, rows with a copy left on disk move to the copy.
+/// Returns the number of removed rows.
+fn remove_wallpapers(conn: &mut SqliteConnection, removed: &[&Wallpaper]) -> Result<usize, Error> {
+    let mut deleted: Vec<&Wallpaper> = Vec::new();
+
+    for w in removed {
+        if !promote_copy(conn, w)? {
+            deleted.push(w);
+        }
+    }
+
+    if deleted.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::delete(
+        schema::wallpapers::table
+            .filter(schema::wallpapers::id.eq_any(deleted.iter().map(|w| &w.id))),
+    )
+    .execute(conn)?;
+
+    for w in &deleted {
+        remove_thumbnail(&w.thumbnail_path);
+    }
+
+    log::info!("Removed {} missing wallpapers", deleted.len());
+
+    Ok(deleted.len())
+}
+
+/// Syncs the `wallpapers` rows of a source with the files on disk. Unchanged files (same
+/// size and mtime) aren't hashed again, moved files keep their row, and rows are only
+/// removed once their file is gone. A cancelled scan still inserts the files it hashed.
+/// Returns every wallpaper of the source.
+pub async fn scan(
+    conn: &mut SqliteConnection,
+    source_id: String,
+    source_path: String,
+    ctx: &mut ScanContext,
+) -> Result<Vec<Wallpaper>, Error> {
+    // An unmounted drive would otherwise look like every file got deleted
+    if !Path::new(&source_path).is_dir() {
+        return Err(Error::NotFound(format!(
+            "Source directory '{source_path}' not found"
+        )));
+    }
+
+    let mut wallpapers_hashmap: WallpapersHashMap = HashMap::new();
+    let mut metadata: MetadataHashMap = HashMap::new();
+    // Every metadata file was read, entries missing from them were removed
+    let mut metadata_complete = true;
+    let mut thumbnail_generation_list: Vec<ThumbnailTask> = Vec::new();
+    let mut copies: Vec<NewDuplicate> = Vec::new();
+
+    let mut known: HashMap<String, Wallpaper> = schema::wallpapers::table
+        .filter(schema::wallpapers::wallpaper_source_id.eq(&source_id))
+        .get_results::<Wallpaper>(conn)?
+        .into_iter()
+        .map(|w| (w.path.clone(), w))
+        .collect();
+    let known_copies: DuplicatesHashMap = schema::duplicates::table
+        .inner_join(schema::wallpapers::table)
+        .filter(schema::duplicates::wallpaper_source_id.eq(&source_id))
+        .select((schema::duplicates::all_columns, schema::wallpapers::path))
+        .get_results::<(Duplicate, String)>(conn)?
+        .into_iter()
+        .map(|(d, original_path)| (d.path.clone(), (d, original_path)))
+        .collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut seen_copies: HashSet<String> = HashSet::new();
+
+    ctx.progress.source_id = Some(source_id.clone());
+    ctx.set_stage(ScanStage::Walking);
+
+    for entry in WalkDir::new(&source_path) {
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        let entry = match entry {
+            Ok(v) if v.path().is_file() => v,
+            Ok(_) => continue,
+            Err(e) => {
+                log::warn!("Failed to walk '{source_path}': {e}");
+                ctx.error(e.to_string());
+                // The directory may have had one
+                metadata_complete = false;
+                continue;
+            }
+        };
+
+        if metadata::is_metadata_file(entry.path()) {
+            metadata_complete &= extract_metadata(&mut metadata, entry.path(), ctx);
+            continue;
+        }
+
+        if !entry.path().extension().is_some_and(is_image_extension) {
+            continue;
+        }
+
+        let path = entry.path().to_string_lossy().to_string();
+
+        ctx.update(|p| p.discovered += 1);
+
+        match sync_file(
+            conn,
+            &source_id,
+            entry.path(),
+            known.get(&path),
+            known_copies.get(&path),
+            &mut thumbnail_generation_list,
+        )? {
+            // The old path is dropped from `known` so it isn't removed below
+            FileSync::Updated {
+                moved_from: Some(old_path),
+            } => {
+                known.remove(&old_path);
+                ctx.update(|p| p.hashed += 1);
+            }
+            FileSync::New(new_wallpaper) => {
+                if wallpapers_hashmap.contains_key(&new_wallpaper.signature) {
+                    seen_copies.insert(path.clone());
+                }
+
+                queue_new(&mut wallpapers_hashmap, &mut copies, *new_wallpaper);
+                ctx.update(|p| p.hashed += 1);
+            }
+            FileSync::Duplicate => {
+                seen_copies.insert(path.clone());
+            }
+            FileSync::Updated { moved_from: None } | FileSync::Skipped => {
+                ctx.update(|p| p.hashed += 1);
+            }
+            FileSync::Unreadable => ctx.error(format!("Failed to read '{path}'")),
+            FileSync::Unchanged => {}
+        }
+
+        seen.insert(path);
+    }
+
+    wallpapers_hashmap
+        .values_mut()
+        .for_each(|w| set_keywords(w, &metadata));
+
+    let inserted = insert_new(conn, &wallpapers_hashmap, &metadata)?;
+    ctx.update(|p| p.inserted += inserted as u32);
+    record_copies(conn, &copies);
+    // A cancelled walk may not have reached every metadata file
+    update_descriptions(
+        conn,
+        &source_id,
+        &metadata,
+        metadata_complete && !ctx.is_cancelled(),
+    )?;
+
+    // Files not reached by a cancelled walk can't be told apart from deleted ones
+    if !ctx.is_cancelled() {
+        // Copies that are gone, or got a row of their own
+        let stale_copies: Vec<&String> = known_copies
+            .keys()
+            .filter(|path| !seen_copies.contains(*path))
+            .collect();
+
+        remove_duplicates(conn, &stale_copies)?;
+
+        // Files that failed to read are still on disk and keep their rows
+        let removed: Vec<&Wallpaper> = known
+            .values()
+            .filter(|w| !seen.contains(&w.path) && !Path::new(&w.path).exists())
+            .collect();
+
+        let removed = remove_wallpapers(conn, &removed)?;
+        ctx.update(|p| p.removed += removed as u32);
+
+        ctx.set_stage(ScanStage::Thumbnails);
+        process_thumbnail_task_list(conn, thumbnail_generation_list, ctx).await;
+    }
+
+    match schema::wallpapers::table
+        .filter(schema::wallpapers::wallpaper_source_id.eq(&source_id))
+        .get_results::<Wallpaper>(conn)
+    {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Syncs single files or directories below a source without walking all of it, used by
+/// the watcher. Paths that no longer exist are left to [`remove_missing`]. Returns whether
+/// any row changed.
+pub async fn sync_paths(
+    conn: &mut SqliteConnection,
+    source_id: &str,
+    paths: &[PathBuf],
+) -> Result<bool, Error> {
+    let mut wallpapers_hashmap: WallpapersHashMap = HashMap::new();
+    // Metadata files are only read by full scans, new files are tagged from their name
+    let metadata: MetadataHashMap = HashMap::new();
+    let mut thumbnail_generation_list: Vec<ThumbnailTask> = Vec::new();
+    let mut copies: Vec<NewDuplicate> = Vec::new();
+    let mut changed = false;
+
+    let files: Vec<PathBuf> = paths
+        .iter()
+        .filter(|p| p.exists())
+        .flat_map(|p| {
+            WalkDir::new(p)
+                .into_iter()
+                .filter_map(|e| e.ok().filter(|x| x.path().is_file()))
+                .map(|e| e.into_path())
+        })
+        .filter(|p| p.extension().is_some_and(is_image_extension))
+        .collect();
+
+    for file in files {
+        let existing = schema::wallpapers::table
+            .filter(schema::wallpapers::path.eq(file.to_string_lossy()))
+            .get_result::<Wallpaper>(conn)
+            .optional()?;
+
+        match sync_file(
+            conn,
+            source_id,
+            &file,
+            existing.as_ref(),
+            None,
+            &mut thumbnail_generation_list,
+        )? {
+            FileSync::Updated { .. } => changed = true,
+            FileSync::New(mut new_wallpaper) => {
+                set_keywords(&mut new_wallpaper, &metadata);
+                queue_new(&mut wallpapers_hashmap, &mut copies, *new_wallpaper);
+            }
+            FileSync::Unchanged
+            | FileSync::Unreadable
+            | FileSync::Duplicate
+            | FileSync::Skipped => {}
+        }
+    }
+
+    if insert_new(conn, &wallpapers_hashmap, &metadata)? > 0 {
+        changed = true;
+    }
+
+    record_copies(conn, &copies);
+
+    // Not cancellable by `cmd_cancel_scan`, and nobody listens for its progress
+    let mut ctx = ScanContext::new(&ScanControl::default(), |_| {});
+    process_thumbnail_task_list(conn, thumbnail_generation_list, &mut ctx).await;
+    ctx.finish();
+
+    Ok(changed)
 }
 
-pub async fn scan_all(conn: &mut SqliteConnection) -> Result<Response<Vec<Wallpaper>>, String> {
+/// Removes the rows of deleted files, and of files below deleted directories since those
+/// only report their own path. Returns whether any row changed.
+pub fn remove_missing(
+    conn: &mut SqliteConnection,
+    source_id: &str,
+    paths: &[PathBuf],
+) -> Result<bool, Error> {
+    let is_missing = |p: &str| {
+        let path = Path::new(p);
+        paths.iter().any(|m| path.starts_with(m)) && !path.exists()
+    };
+
+    let copies = schema::duplicates::table
+        .filter(schema::duplicates::wallpaper_source_id.eq(source_id))
+        .select(schema::duplicates::path)
+        .get_results::<String>(conn)?;
+
+    remove_duplicates(
+        conn,
+        &copies.iter().filter(|p| is_missing(p)).collect::<Vec<_>>(),
+    )?;
+
+    let rows = schema::wallpapers::table
+        .filter(schema::wallpapers::wallpaper_source_id.eq(source_id))
+        .get_results::<Wallpaper>(conn)?;
+
+    let removed: Vec<&Wallpaper> = rows.iter().filter(|w| is_missing(&w.path)).collect();
+
+    remove_wallpapers(conn, &removed)?;
+
+    Ok(!removed.is_empty())
+}
+
+pub async fn scan_all(
+    conn: &mut SqliteConnection,
+    ctx: &mut ScanContext,
+) -> Result<Vec<Wallpaper>, Error> {
     let mut wallpapers_list: Vec<Wallpaper> = Vec::new();
 
     let wallpaper_sources: Vec<WallpaperSource> =
         match schema::wallpaper_sources::table.get_results::<WallpaperSource>(conn) {
             Ok(v) => v,
             Err(e) => {
-                return Err(e.to_string());
+                return Err(e.into());
             }
         };
 
     for source in wallpaper_sources {
-        match scan(conn, source.id, source.path).await {
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        match scan(conn, source.id, source.path, ctx).await {
             Ok(wallpapers) => {
                 wallpapers_list.extend(wallpapers);
             }
@@ -241,5 +995,40 @@ pub async fn scan_all(conn: &mut SqliteConnection) -> Result<Response<Vec<Wallpa
         }
     }
 
-    Ok(Response::new(wallpapers_list))
+    Ok(wallpapers_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn recorded(control: &ScanControl) -> (ScanContext, Arc<Mutex<Vec<ScanStage>>>) {
+        let stages = Arc::new(Mutex::new(Vec::new()));
+        let sink = stages.clone();
+        let ctx = ScanContext::new(control, move |p| sink.lock().unwrap().push(p.stage));
+
+        (ctx, stages)
+    }
+
+    #[test]
+    fn dropped_run_reports_failed() {
+        let (mut ctx, stages) = recorded(&ScanControl::default());
+        ctx.set_stage(ScanStage::Thumbnails);
+        drop(ctx);
+
+        assert_eq!(
+            *stages.lock().unwrap(),
+            [ScanStage::Thumbnails, ScanStage::Failed]
+        );
+    }
+
+    #[test]
+    fn finished_run_reports_once() {
+        let (mut ctx, stages) = recorded(&ScanControl::default());
+        ctx.finish();
+        drop(ctx);
+
+        assert_eq!(*stages.lock().unwrap(), [ScanStage::Done]);
+    }
 }
@@ -0,0 +1,193 @@
+use crate::backend::{self, Assignment, Mode};
+use crate::error::Error;
+use crate::hyprpaper::{self, Monitor};
+use crate::utils::fs::get_cache_dir;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+const JPEG_QUALITY: u8 = 90;
+
+type Rect = (u32, u32, u32, u32);
+
+fn cache_dir() -> PathBuf {
+    let mut path = get_cache_dir();
+    path.push("span");
+
+    if !path.exists() {
+        if let Err(e) = std::fs::create_dir(&path) {
+            log::error!("Failed to create span dir: {e}");
+        }
+    }
+
+    path
+}
+
+/// Slices depend on every spanned monitor, so the whole layout is part of the key.
+fn layout_key(monitors: &[&Monitor]) -> String {
+    let layout: Vec<String> = monitors
+        .iter()
+        .map(|m| {
+            format!(
+                "{}:{},{}:{}x{}@{}:{}",
+                m.name, m.x, m.y, m.width, m.height, m.scale, m.transform
+            )
+        })
+        .collect();
+
+    blake3::hash(layout.join(";").as_bytes()).to_hex()[..16].to_string()
+}
+
+/// Part of an `image_size` image shown on each monitor, when the image covers the bounding
+/// box of all of them and is centered on it.
+fn slice_rects((width, height): (u32, u32), monitors: &[&Monitor]) -> Vec<Rect> {
+    let rects: Vec<(f64, f64, f64, f64)> = monitors
+        .iter()
+        .map(|m| {
+            let (w, h) = m.logical_size();
+            (f64::from(m.x), f64::from(m.y), w, h)
+        })
+        .collect();
+
+    let min_x = rects.iter().map(|r| r.0).fold(f64::MAX, f64::min);
+    let min_y = rects.iter().map(|r| r.1).fold(f64::MAX, f64::min);
+    let max_x = rects.iter().map(|r| r.0 + r.2).fold(f64::MIN, f64::max);
+    let max_y = rects.iter().map(|r| r.1 + r.3).fold(f64::MIN, f64::max);
+
+    let (box_width, box_height) = (max_x - min_x, max_y - min_y);
+    let (width, height) = (f64::from(width), f64::from(height));
+
+    // Layout units per image pixel
+    let k = f64::max(box_width / width, box_height / height);
+    let offset_x = (width * k - box_width) / 2.0;
+    let offset_y = (height * k - box_height) / 2.0;
+
+    rects
+        .into_iter()
+        .map(|(x, y, w, h)| {
+            let sx = ((x - min_x + offset_x) / k).round().clamp(0.0, width - 1.0);
+            let sy = ((y - min_y + offset_y) / k)
+                .round()
+                .clamp(0.0, height - 1.0);
+            let sw = (w / k).round().clamp(1.0, width - sx);
+            let sh = (h / k).round().clamp(1.0, height - sy);
+
+            (sx as u32, sy as u32, sw as u32, sh as u32)
+        })
+        .collect()
+}
+
+/// Keeps one span per screen, older slices are never shown again.
+fn remove_old_slices(screen: &str, keep: &PathBuf) {
+    let Ok(entries) = std::fs::read_dir(cache_dir()) else {
+        return;
+    };
+
+    let prefix = format!("{screen}_");
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        if &path != keep && entry.file_name().to_string_lossy().starts_with(&prefix) {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("Failed to remove '{}': {e}", path.to_string_lossy());
+            }
+        }
+    }
+}
+
+fn write_slice(
+    image: &DynamicImage,
+    rect: Rect,
+    monitor: &Monitor,
+    dest: &PathBuf,
+) -> Result<(), Error> {
+    let (x, y, w, h) = rect;
+    let (width, height) = monitor.pixel_size();
+
+    let slice = image
+        .crop_imm(x, y, w, h)
+        .resize_exact(width, height, FilterType::CatmullRom)
+        .into_rgb8();
+
+    // Renamed into place once complete, `assignments` takes any existing slice as cached
+    let partial = dest.with_extension("tmp");
+
+    let result = File::create(&partial).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+
+        JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY)
+            .encode_image(&slice)
+            .map_err(std::io::Error::other)?;
+        writer.flush()?;
+
+        std::fs::rename(&partial, dest)
+    });
+
+    if result.is_err() {
+        if let Err(e) = std::fs::remove_file(&partial) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove '{}': {e}", partial.to_string_lossy());
+            }
+        }
+    }
+
+    Ok(result?)
+}
+
+/// One assignment per screen showing its slice of `path`, cut to the monitor layout Hyprland
+/// reports. Slices are cached by `signature` and layout, and set like `default` wallpapers.
+pub fn assignments(
+    path: &str,
+    signature: &str,
+    screens: &[String],
+) -> Result<Vec<Assignment>, Error> {
+    let monitors = hyprpaper::monitors().map_err(backend::Error::from)?;
+
+    let spanned: Vec<&Monitor> = monitors
+        .iter()
+        .filter(|m| screens.contains(&m.name))
+        .collect();
+
+    if spanned.is_empty() {
+        return Err(Error::NotFound(format!(
+            "None of the screens '{}' are connected",
+            screens.join(", ")
+        )));
+    }
+
+    let layout = layout_key(&spanned);
+    let dests: Vec<PathBuf> = spanned
+        .iter()
+        .map(|m| cache_dir().join(format!("{}_{signature}_{layout}.jpeg", m.name)))
+        .collect();
+
+    if dests.iter().any(|d| !d.exists()) {
+        let image = match ImageReader::open(path)?.with_guessed_format()?.decode() {
+            Ok(v) => v,
+            Err(e) => return Err(Error::Io(std::io::Error::other(e))),
+        };
+
+        let rects = slice_rects((image.width(), image.height()), &spanned);
+
+        for ((monitor, rect), dest) in spanned.iter().zip(rects).zip(&dests) {
+            write_slice(&image, rect, monitor, dest)?;
+            remove_old_slices(&monitor.name, dest);
+        }
+    }
+
+    Ok(spanned
+        .iter()
+        .zip(dests)
+        .map(|(m, dest)| {
+            Assignment::new(
+                m.name.clone(),
+                dest.to_string_lossy().to_string(),
+                Mode::Default,
+            )
+        })
+        .collect())
+}
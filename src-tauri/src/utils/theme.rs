@@ -0,0 +1,302 @@
+use crate::database::models::Wallpaper;
+use crate::database::settings::{get_setting, THEME_DIR, THEME_EXPORT, THEME_RELOAD_COMMAND};
+use crate::error::Error;
+use crate::schema;
+use crate::utils::fs::{expand_home, get_cache_dir};
+use crate::utils::hooks;
+use crate::utils::palette::{self, Color};
+use diesel::prelude::*;
+use image::ImageReader;
+use std::path::{Path, PathBuf};
+
+/// Files in `<theme dir>/templates` are rendered to `<theme dir>` under the same name.
+const TEMPLATES_DIR: &str = "templates";
+
+const BLACK: Color = [0, 0, 0];
+const WHITE: Color = [255, 255, 255];
+
+/// Text stays readable on the background below this luminance, and above it for the rest.
+const MAX_BACKGROUND_LUMINANCE: f64 = 0.1;
+const MIN_FOREGROUND_LUMINANCE: f64 = 0.85;
+const MIN_COLOR_LUMINANCE: f64 = 0.3;
+
+/// Colors derived from a wallpaper's palette, `colors` are the 16 terminal colors pywal writes.
+struct Theme {
+    background: Color,
+    foreground: Color,
+    accent: Color,
+    colors: [Color; 16],
+}
+
+/// Darkens `color` until its luminance is at most `max`, keeping its tint.
+fn at_most(color: Color, max: f64) -> Color {
+    let luminance = palette::luminance(color);
+
+    if luminance <= max {
+        return color;
+    }
+
+    palette::mix(color, BLACK, 1.0 - max / luminance)
+}
+
+/// Lightens `color` until its luminance is at least `min`.
+fn at_least(color: Color, min: f64) -> Color {
+    let luminance = palette::luminance(color);
+
+    if luminance >= min {
+        return color;
+    }
+
+    palette::mix(color, WHITE, (min - luminance) / (1.0 - luminance))
+}
+
+impl Theme {
+    fn from_palette(colors: &[Color]) -> Self {
+        let by_luminance =
+            |a: &Color, b: &Color| palette::luminance(*a).total_cmp(&palette::luminance(*b));
+
+        let darkest = colors.iter().copied().min_by(by_luminance).unwrap_or(BLACK);
+        let lightest = colors.iter().copied().max_by(by_luminance).unwrap_or(WHITE);
+
+        let background = at_most(darkest, MAX_BACKGROUND_LUMINANCE);
+        let foreground = at_least(lightest, MIN_FOREGROUND_LUMINANCE);
+        let accent = colors
+            .iter()
+            .copied()
+            .max_by(|a, b| palette::vividness(*a).total_cmp(&palette::vividness(*b)))
+            .map(|c| at_least(c, MIN_COLOR_LUMINANCE))
+            .unwrap_or(foreground);
+
+        // color1 to color6 in order of dominance, repeated when the palette is short
+        let normal: Vec<Color> = if colors.is_empty() {
+            vec![accent; 6]
+        } else {
+            colors
+                .iter()
+                .cycle()
+                .take(6)
+                .map(|c| at_least(*c, MIN_COLOR_LUMINANCE))
+                .collect()
+        };
+
+        let mut terminal = [BLACK; 16];
+        terminal[0] = background;
+        terminal[7] = palette::mix(foreground, background, 0.25);
+        terminal[8] = palette::mix(background, WHITE, 0.25);
+        terminal[15] = foreground;
+
+        for (i, color) in normal.into_iter().enumerate() {
+            terminal[i + 1] = color;
+            terminal[i + 9] = palette::mix(color, WHITE, 0.15);
+        }
+
+        Self {
+            background,
+            foreground,
+            accent,
+            colors: terminal,
+        }
+    }
+
+    /// Template variables, `{{name}}` is `#rrggbb` and `{{name.strip}}` is `rrggbb`.
+    fn variables(&self) -> Vec<(String, Color)> {
+        let mut variables = vec![
+            (String::from("background"), self.background),
+            (String::from("foreground"), self.foreground),
+            (String::from("cursor"), self.foreground),
+            (String::from("accent"), self.accent),
+        ];
+
+        variables.extend(
+            self.colors
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (format!("color{i}"), *c)),
+        );
+
+        variables
+    }
+
+    fn render(&self, template: &str, wallpaper: &str) -> String {
+        let mut output = template.replace("{{wallpaper}}", wallpaper);
+
+        for (name, color) in self.variables() {
+            let hex = palette::to_hex(color);
+
+            output = output
+                .replace(&format!("{{{{{name}.strip}}}}"), &hex[1..])
+                .replace(&format!("{{{{{name}}}}}"), &hex);
+        }
+
+        output
+    }
+
+    fn json(&self, wallpaper: &str, colors: &[Color]) -> serde_json::Value {
+        serde_json::json!({
+            "wallpaper": wallpaper,
+            "background": palette::to_hex(self.background),
+            "foreground": palette::to_hex(self.foreground),
+            "accent": palette::to_hex(self.accent),
+            "palette": colors.iter().map(|c| palette::to_hex(*c)).collect::<Vec<String>>(),
+        })
+    }
+
+    /// Same layout as `~/.cache/wal/colors.json`.
+    fn pywal(&self, wallpaper: &str) -> serde_json::Value {
+        let colors: serde_json::Map<String, serde_json::Value> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (format!("color{i}"), palette::to_hex(*c).into()))
+            .collect();
+
+        serde_json::json!({
+            "wallpaper": wallpaper,
+            "alpha": "100",
+            "special": {
+                "background": palette::to_hex(self.background),
+                "foreground": palette::to_hex(self.foreground),
+                "cursor": palette::to_hex(self.foreground),
+            },
+            "colors": colors,
+        })
+    }
+
+    fn css(&self) -> String {
+        let lines: Vec<String> = self
+            .variables()
+            .into_iter()
+            .map(|(name, c)| format!("    --{name}: {};", palette::to_hex(c)))
+            .collect();
+
+        format!(":root {{\n{}\n}}\n", lines.join("\n"))
+    }
+
+    /// GTK doesn't support custom properties, waybar and GTK themes use `@define-color`.
+    fn gtk_css(&self) -> String {
+        self.variables()
+            .into_iter()
+            .map(|(name, c)| format!("@define-color {name} {};\n", palette::to_hex(c)))
+            .collect()
+    }
+
+    /// Variables for `source = .../hyprland.conf`, e.g. `col.active_border = $accent`.
+    fn hyprland(&self) -> String {
+        self.variables()
+            .into_iter()
+            .map(|(name, c)| format!("${name} = rgb({})\n", &palette::to_hex(c)[1..]))
+            .collect()
+    }
+}
+
+fn theme_dir(conn: &mut SqliteConnection) -> Result<PathBuf, Error> {
+    let dir = match get_setting(conn, THEME_DIR)? {
+        Some(v) => expand_home(&v),
+        None => get_cache_dir().join("theme"),
+    };
+
+    std::fs::create_dir_all(&dir)?;
+
+    Ok(dir)
+}
+
+/// Replaces `name` in one step, so programs watching the file never read half of it.
+fn write_file(dir: &Path, name: &str, contents: &str) -> Result<(), Error> {
+    let tmp = dir.join(format!(".{name}.tmp"));
+
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, dir.join(name))?;
+
+    Ok(())
+}
+
+fn render_templates(theme: &Theme, dir: &Path, wallpaper: &str) -> Result<(), Error> {
+    let entries = match std::fs::read_dir(dir.join(TEMPLATES_DIR)) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if !entry.file_type().is_ok_and(|t| t.is_file()) || name.starts_with('.') {
+            continue;
+        }
+
+        match std::fs::read_to_string(entry.path()) {
+            Ok(template) => write_file(dir, &name, &theme.render(&template, wallpaper))?,
+            Err(e) => log::warn!("Failed to read template '{name}': {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// Palette stored by the scan, extracted and stored now for wallpapers whose thumbnail pass
+/// hasn't run yet.
+fn palette_of(conn: &mut SqliteConnection, wallpaper: &Wallpaper) -> Result<Vec<Color>, Error> {
+    if let Some(v) = &wallpaper.palette {
+        return Ok(palette::parse(v));
+    }
+
+    let image = match ImageReader::open(&wallpaper.path)?
+        .with_guessed_format()?
+        .decode()
+    {
+        Ok(v) => v,
+        Err(e) => return Err(Error::Io(std::io::Error::other(e))),
+    };
+
+    let colors = palette::extract(&image);
+
+    diesel::update(schema::wallpapers::table.find(&wallpaper.id))
+        .set(schema::wallpapers::palette.eq(palette::to_string(&colors)))
+        .execute(conn)?;
+
+    Ok(colors)
+}
+
+/// Writes the theme files for `wallpaper` when `theme.export` is enabled, then runs
+/// `theme.reload_command` when set.
+pub fn export(conn: &mut SqliteConnection, wallpaper: &Wallpaper) -> Result<(), Error> {
+    if get_setting(conn, THEME_EXPORT)?.as_deref() != Some("true") {
+        return Ok(());
+    }
+
+    let colors = palette_of(conn, wallpaper)?;
+    let theme = Theme::from_palette(&colors);
+    let dir = theme_dir(conn)?;
+    let path = wallpaper.path.as_str();
+
+    let json = |value: serde_json::Value| format!("{value:#}\n");
+
+    write_file(&dir, "theme.json", &json(theme.json(path, &colors)))?;
+    write_file(&dir, "colors.json", &json(theme.pywal(path)))?;
+    write_file(&dir, "colors.css", &theme.css())?;
+    write_file(&dir, "colors-gtk.css", &theme.gtk_css())?;
+    write_file(&dir, "hyprland.conf", &theme.hyprland())?;
+    render_templates(&theme, &dir, path)?;
+
+    log::debug!("theme of '{path}' written to '{}'", dir.to_string_lossy());
+
+    if let Some(command) = get_setting(conn, THEME_RELOAD_COMMAND)?.filter(|c| !c.is_empty()) {
+        let env = vec![
+            ("HYPR_NITROGEN_THEME_DIR", dir.to_string_lossy().to_string()),
+            ("HYPR_NITROGEN_WALLPAPER", path.to_string()),
+        ];
+
+        hooks::spawn("theme reload", &command, env, hooks::timeout(conn));
+    }
+
+    Ok(())
+}
+
+/// [`export`] for the wallpaper with `id`.
+pub fn export_by_id(conn: &mut SqliteConnection, id: &str) -> Result<(), Error> {
+    let wallpaper = schema::wallpapers::table
+        .find(id)
+        .get_result::<Wallpaper>(conn)?;
+
+    export(conn, &wallpaper)
+}
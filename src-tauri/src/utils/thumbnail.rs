@@ -0,0 +1,144 @@
+use crate::config;
+use crate::utils::{palette, phash};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageError, ImageReader};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const JPEG_QUALITY: u8 = 85;
+
+fn decode(src: &Path) -> Result<DynamicImage, ImageError> {
+    ImageReader::open(src)?.with_guessed_format()?.decode()
+}
+
+/// Writes a cover-cropped JPEG thumbnail of the decoded image to `dest`.
+fn generate_native(image: &DynamicImage, dest: &Path) -> Result<(), ImageError> {
+    let size = &config::get().thumbnails;
+    let thumbnail = image
+        .resize_to_fill(size.width.get(), size.height.get(), FilterType::Triangle)
+        .into_rgb8();
+
+    // A thumbnail cut short by a crash would otherwise be shown as is
+    let partial = dest.with_extension("tmp");
+
+    let result = File::create(&partial)
+        .map_err(ImageError::from)
+        .and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY).encode_image(&thumbnail)?;
+            writer.flush()?;
+            Ok(())
+        })
+        .and_then(|_| Ok(std::fs::rename(&partial, dest)?));
+
+    if result.is_err() {
+        if let Err(e) = std::fs::remove_file(&partial) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove '{}': {e}", partial.to_string_lossy());
+            }
+        }
+    }
+
+    result
+}
+
+/// Fallback for formats the native decoders can't read, only used when `magick` is installed.
+fn generate_magick(src: &Path, dest: &Path) -> Result<(), String> {
+    let size = &config::get().thumbnails;
+    let size = format!("{}x{}", size.width, size.height);
+
+    match std::process::Command::new("magick")
+        .arg(src.as_os_str())
+        .arg("-thumbnail")
+        .arg(format!("{size}^"))
+        .arg("-gravity")
+        .arg("center")
+        .arg("-extent")
+        .arg(&size)
+        .arg("-format")
+        .arg("jpeg")
+        .arg(dest.as_os_str())
+        .output()
+    {
+        Ok(cmd) => {
+            if cmd.status.success() {
+                Ok(())
+            } else {
+                Err(String::from_utf8_lossy(&cmd.stderr).trim().to_string())
+            }
+        }
+        Err(e) => Err(format!("Failed to run magick command: {e}")),
+    }
+}
+
+/// Outcome of [`process`].
+pub struct Processed {
+    /// False when no decoder could write the thumbnail
+    pub thumbnail: bool,
+    pub phash: Option<i64>,
+    pub palette: Option<String>,
+}
+
+fn generate(src: &Path, dest: &Path, image: &Result<DynamicImage, ImageError>) -> bool {
+    let native_error = match image {
+        Ok(image) => match generate_native(image, dest) {
+            Ok(_) => {
+                log::debug!("thumbnail generated: {}", dest.to_string_lossy());
+                return true;
+            }
+            Err(e) => e.to_string(),
+        },
+        Err(e) => e.to_string(),
+    };
+
+    // Don't leave a half written file behind, it would never be regenerated
+    if dest.exists() {
+        if let Err(e) = std::fs::remove_file(dest) {
+            log::warn!("Failed to remove '{}': {e}", dest.to_string_lossy());
+        }
+    }
+
+    match generate_magick(src, dest) {
+        Ok(_) => {
+            log::debug!(
+                "thumbnail generated with magick: {}",
+                dest.to_string_lossy()
+            );
+            true
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to generate thumbnail for '{}': {native_error}, {e}",
+                src.to_string_lossy()
+            );
+            false
+        }
+    }
+}
+
+/// Decodes `src` once for its thumbnail, written to `dest` when set, and its perceptual
+/// hash and palette when `analyze` is set. Images only `magick` can read get neither.
+pub fn process(src: &Path, dest: Option<&Path>, analyze: bool) -> Processed {
+    let image = decode(src);
+
+    let (phash, palette) = match (&image, analyze) {
+        (Ok(image), true) => (
+            Some(phash::dhash(image)),
+            Some(palette::to_string(&palette::extract(image))),
+        ),
+        _ => (None, None),
+    };
+
+    let thumbnail = match dest {
+        Some(dest) => generate(src, dest, &image),
+        None => true,
+    };
+
+    Processed {
+        thumbnail,
+        phash,
+        palette,
+    }
+}
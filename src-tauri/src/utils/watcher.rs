@@ -0,0 +1,229 @@
+use crate::database::connection::DbPool;
+use crate::database::models::*;
+use crate::database::settings::{get_setting, WATCH_SOURCES};
+use crate::error::Error;
+use crate::schema;
+use crate::utils::scan::{remove_missing, sync_paths};
+use diesel::prelude::*;
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::async_runtime;
+
+/// Quiet time before a batch of events is handled, so bulk copies end up in one batch.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often the watched paths are compared against the active sources and the setting.
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(5);
+/// Deleted paths wait this long before their rows are removed. A move can be reported in
+/// two batches, the new path has to be matched by content before the old row is gone.
+const REMOVAL_DELAY: Duration = Duration::from_secs(5);
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A deleted path waiting for [`REMOVAL_DELAY`].
+struct PendingRemoval {
+    source_id: String,
+    path: PathBuf,
+    since: Instant,
+}
+
+/// Active source paths by id, empty while watching is disabled.
+fn wanted_sources(conn: &mut SqliteConnection) -> Result<HashMap<String, PathBuf>, Error> {
+    if get_setting(conn, WATCH_SOURCES)?.as_deref() != Some("true") {
+        return Ok(HashMap::new());
+    }
+
+    Ok(schema::wallpaper_sources::table
+        .filter(schema::wallpaper_sources::active.eq(true))
+        .get_results::<WallpaperSource>(conn)?
+        .into_iter()
+        .map(|s| (s.id, PathBuf::from(s.path)))
+        .collect())
+}
+
+/// Watches and unwatches paths until `watched` matches the wanted sources.
+fn reconcile(
+    conn: &mut SqliteConnection,
+    debouncer: &mut Debouncer<RecommendedWatcher>,
+    watched: &mut HashMap<String, PathBuf>,
+    failed: &mut HashSet<PathBuf>,
+) -> Result<(), Error> {
+    let wanted = wanted_sources(conn)?;
+
+    watched.retain(|id, path| {
+        if wanted.get(id) == Some(path) {
+            return true;
+        }
+
+        if let Err(e) = debouncer.watcher().unwatch(path) {
+            log::warn!("Failed to unwatch '{}': {e}", path.to_string_lossy());
+        }
+
+        log::info!("Stopped watching '{}'", path.to_string_lossy());
+        false
+    });
+
+    for (id, path) in wanted {
+        if watched.contains_key(&id) {
+            continue;
+        }
+
+        match debouncer.watcher().watch(&path, RecursiveMode::Recursive) {
+            Ok(_) => {
+                log::info!("Watching '{}'", path.to_string_lossy());
+                failed.remove(&path);
+                watched.insert(id, path);
+            }
+            // Retried on every reconcile, e.g. until a network mount is back
+            Err(e) => {
+                if failed.insert(path.clone()) {
+                    log::warn!("Failed to watch '{}': {e}", path.to_string_lossy());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Syncs the rows below every changed path that still exists and queues the others,
+/// returns whether any row changed.
+fn handle_events(
+    conn: &mut SqliteConnection,
+    watched: &HashMap<String, PathBuf>,
+    events: Vec<DebouncedEvent>,
+    pending: &mut Vec<PendingRemoval>,
+) -> bool {
+    let mut paths_by_source: HashMap<&String, Vec<PathBuf>> = HashMap::new();
+
+    for event in events {
+        // The innermost source wins when sources are nested
+        let source = watched
+            .iter()
+            .filter(|(_, root)| event.path.starts_with(root))
+            .max_by_key(|(_, root)| root.as_os_str().len());
+
+        let Some((id, _)) = source else {
+            continue;
+        };
+
+        if event.path.exists() {
+            paths_by_source.entry(id).or_default().push(event.path);
+        } else {
+            pending.push(PendingRemoval {
+                source_id: id.clone(),
+                path: event.path,
+                since: Instant::now(),
+            });
+        }
+    }
+
+    let mut changed = false;
+
+    for (source_id, paths) in paths_by_source {
+        match async_runtime::block_on(sync_paths(conn, source_id, &paths)) {
+            Ok(v) => changed |= v,
+            Err(e) => log::error!("Failed to sync source '{source_id}': {e}"),
+        }
+    }
+
+    changed
+}
+
+/// Removes the rows of pending paths older than [`REMOVAL_DELAY`], returns whether any
+/// row changed.
+fn handle_removals(conn: &mut SqliteConnection, pending: &mut Vec<PendingRemoval>) -> bool {
+    let (due, waiting): (Vec<PendingRemoval>, Vec<PendingRemoval>) = pending
+        .drain(..)
+        .partition(|p| p.since.elapsed() >= REMOVAL_DELAY);
+
+    *pending = waiting;
+
+    let mut paths_by_source: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for removal in due {
+        paths_by_source
+            .entry(removal.source_id)
+            .or_default()
+            .push(removal.path);
+    }
+
+    let mut changed = false;
+
+    for (source_id, paths) in paths_by_source {
+        match remove_missing(conn, &source_id, &paths) {
+            Ok(v) => changed |= v,
+            Err(e) => log::error!("Failed to sync source '{source_id}': {e}"),
+        }
+    }
+
+    changed
+}
+
+/// Keeps the active sources in sync while the `watch_sources` setting is on, calling
+/// `on_change` after wallpapers were added, updated or removed.
+pub fn run<F: FnMut()>(pool: DbPool, mut on_change: F) -> Result<(), Error> {
+    let (tx, rx) = mpsc::channel::<DebounceEventResult>();
+
+    let mut debouncer = match new_debouncer(DEBOUNCE_TIMEOUT, tx) {
+        Ok(v) => v,
+        Err(e) => return Err(Error::Io(std::io::Error::other(e))),
+    };
+
+    let mut watched: HashMap<String, PathBuf> = HashMap::new();
+    let mut failed: HashSet<PathBuf> = HashSet::new();
+    let mut pending: Vec<PendingRemoval> = Vec::new();
+    let mut last_reconcile: Option<Instant> = None;
+
+    loop {
+        if last_reconcile.is_none_or(|t| t.elapsed() >= RECONCILE_INTERVAL) {
+            match pool.get() {
+                Ok(mut conn) => {
+                    if let Err(e) = reconcile(&mut conn, &mut debouncer, &mut watched, &mut failed)
+                    {
+                        log::error!("Failed to update watched sources: {e}");
+                    }
+                }
+                Err(e) => log::error!("Failed to update watched sources: {e}"),
+            }
+
+            last_reconcile = Some(Instant::now());
+        }
+
+        let events = match rx.recv_timeout(TICK_INTERVAL) {
+            Ok(Ok(events)) => events,
+            Ok(Err(e)) => {
+                log::error!("Watcher error: {e}");
+                Vec::new()
+            }
+            Err(RecvTimeoutError::Timeout) => Vec::new(),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        };
+
+        if events.is_empty() && pending.is_empty() {
+            continue;
+        }
+
+        match pool.get() {
+            Ok(mut conn) => {
+                let synced = handle_events(&mut conn, &watched, events, &mut pending);
+                let removed = handle_removals(&mut conn, &mut pending);
+
+                if synced || removed {
+                    on_change();
+                }
+            }
+            Err(e) => log::error!("Failed to sync sources: {e}"),
+        }
+    }
+}
+
+pub fn spawn<F: FnMut() + Send + 'static>(pool: DbPool, on_change: F) {
+    std::thread::spawn(move || {
+        if let Err(e) = run(pool, on_change) {
+            log::error!("Source watcher stopped: {e}");
+        }
+    });
+}
@@ -1,5 +1,8 @@
 import {
+    BackIcon,
+    ForwardIcon,
     RandomIcon,
+    RemoveIcon,
     RestoreIcon,
     SaveIcon,
     ScanIcon,
@@ -8,41 +11,78 @@ import {
 } from '@icons';
 import * as ipc from '@ipc';
 import { debounce } from '@solid-primitives/scheduled';
-import { createSignal, For, onCleanup, onMount } from 'solid-js';
+import { createSignal, For, onCleanup, onMount, Show } from 'solid-js';
 import toast from 'solid-toast';
 import { useGlobalContext } from '@/store';
 
 export function Header() {
     const {
         showSettings,
-        filteredItems,
+        totalWallpapers,
         selectedScreen,
         selectedMode,
         selectedWallpaper,
         searchQuery,
         debouncedSearchQuery,
         activeWallpapers,
-        wallpapers,
+        refreshWallpapers,
     } = useGlobalContext();
     const [screens, setScreens] = createSignal<string[]>();
-    const [scanButtonActive, setScanButtonActive] = createSignal(true);
+    const [scanning, setScanning] = createSignal(false);
 
-    const wallpaper_modes: ipc.types.Mode[] = ['default', 'contain', 'tile'];
+    const wallpaper_modes: ipc.types.Mode[] = [
+        'default',
+        'cover',
+        'contain',
+        'contain:#000000',
+        'contain:blur',
+        'stretch',
+        'center',
+        'tile',
+        'span',
+    ];
 
     const debouncedPerformSearch = debounce(
         (query: string) => debouncedSearchQuery.set(query),
         300,
     );
 
-    onMount(async () => {
+    async function refreshScreens() {
         const screensRes = await ipc.cmd.get_screens().catch(ipc.handleError);
         if (!screensRes) return;
 
         setScreens(screensRes.data);
-    });
+    }
+
+    const unlistenMonitorsChanged = ipc.events.monitors_changed(
+        async (event) => {
+            await refreshScreens();
+
+            if (!event.connected) return;
+
+            const activeWallpapersRes = await ipc.cmd
+                .get_active_wallpapers()
+                .catch(ipc.handleError);
+
+            if (!activeWallpapersRes) return;
+
+            activeWallpapers.set(activeWallpapersRes.data);
+        },
+    );
+
+    // also covers scans started from the settings menu
+    const unlistenScanProgress = ipc.events.scan_progress((progress) =>
+        setScanning(
+            progress.stage === 'walking' || progress.stage === 'thumbnails',
+        ),
+    );
+
+    onMount(refreshScreens);
 
     onCleanup(() => {
         debouncedPerformSearch.clear();
+        unlistenMonitorsChanged.then((unlisten) => unlisten());
+        unlistenScanProgress.then((unlisten) => unlisten());
     });
 
     async function setWallpaper(
@@ -78,6 +118,23 @@ export function Header() {
         }
     }
 
+    async function stepHistory(forward: boolean) {
+        const args = { screen: selectedScreen.get() };
+        const stepRes = await (
+            forward ? ipc.cmd.history_forward(args) : ipc.cmd.history_back(args)
+        ).catch(ipc.handleError);
+
+        if (!stepRes) return;
+
+        const activeWallpapersRes = await ipc.cmd
+            .get_active_wallpapers()
+            .catch(ipc.handleError);
+
+        if (!activeWallpapersRes) return;
+
+        activeWallpapers.set(activeWallpapersRes.data);
+    }
+
     function handleSearchChange(e: Event) {
         const value = (e.target as HTMLInputElement).value;
 
@@ -96,18 +153,15 @@ export function Header() {
     }
 
     function scanAll() {
-        setScanButtonActive(false);
-        toast
-            .promise(ipc.cmd.scan_all_sources(), {
-                loading: 'Scanning all sources...',
-                success: 'Scan complete',
-                error: 'Scan failed',
-            })
-            .then((res) => {
-                wallpapers.set(res.data);
-            })
+        setScanning(true);
+        ipc.withScanProgress(ipc.cmd.scan_all_sources)
+            .then(refreshWallpapers)
             .catch(ipc.handleError)
-            .finally(() => setScanButtonActive(true));
+            .finally(() => setScanning(false));
+    }
+
+    async function cancelScan() {
+        await ipc.cmd.cancel_scan().catch(ipc.handleError);
     }
 
     return (
@@ -158,12 +212,43 @@ export function Header() {
                     title='Select wallpaper mode'
                 >
                     <For each={wallpaper_modes}>
-                        {(x) => <option value={x}>{x}</option>}
+                        {(x) => (
+                            <option value={x}>
+                                {x.startsWith('contain:#')
+                                    ? 'contain:color'
+                                    : x}
+                            </option>
+                        )}
                     </For>
                 </select>
+                <Show when={selectedMode.get().startsWith('contain:#')}>
+                    <input
+                        type='color'
+                        value={selectedMode.get().slice('contain:'.length)}
+                        onInput={(e) => {
+                            const color = (e.target as HTMLInputElement).value;
+                            selectedMode.set(
+                                `contain:${color}` as ipc.types.Mode,
+                            );
+                        }}
+                        title='Letterbox color'
+                    />
+                </Show>
             </div>
-            <span>{filteredItems().length} wallpapers</span>
+            <span>{totalWallpapers.get()} wallpapers</span>
             <div class='header-right'>
+                <button
+                    onClick={() => stepHistory(false)}
+                    title='Bring back the previous wallpaper'
+                >
+                    <BackIcon />
+                </button>
+                <button
+                    onClick={() => stepHistory(true)}
+                    title='Go forward in the wallpaper history'
+                >
+                    <ForwardIcon />
+                </button>
                 <button
                     onClick={() => setWallpaper(false, true)}
                     title='Select a random wallpaper'
@@ -177,11 +262,12 @@ export function Header() {
                     <RestoreIcon />
                 </button>
                 <button
-                    disabled={!scanButtonActive()}
-                    onClick={scanAll}
-                    title='Scan all sources'
+                    onClick={() => (scanning() ? cancelScan() : scanAll())}
+                    title={scanning() ? 'Cancel scan' : 'Scan all sources'}
                 >
-                    <ScanIcon />
+                    <Show when={scanning()} fallback={<ScanIcon />}>
+                        <RemoveIcon />
+                    </Show>
                 </button>
                 <button
                     onClick={() => showSettings.set(true)}
@@ -8,10 +8,20 @@ import { useGlobalContext } from '@/store';
 import { createSignalObject } from '@/utils';
 
 export function Settings() {
-    const { wallpapers, showSettings } = useGlobalContext();
+    const { refreshWallpapers, showSettings } = useGlobalContext();
     const wallpaperSources = createSignalObject<ipc.types.WallpaperSource[]>(
         [],
     );
+    const backend = createSignalObject<ipc.types.Backend>('hyprpaper');
+    const watchSources = createSignalObject<boolean>(false);
+    const prescale = createSignalObject<boolean>(false);
+    const themeExport = createSignalObject<boolean>(false);
+    const backends: ipc.types.Backend[] = [
+        'hyprpaper',
+        'swww',
+        'swaybg',
+        'wpaperd',
+    ];
 
     onMount(async () => {
         const wallpaperSourcesRes = await ipc.cmd
@@ -21,8 +31,62 @@ export function Settings() {
         if (!wallpaperSourcesRes) return;
 
         wallpaperSources.set(wallpaperSourcesRes.data);
+
+        const settingsRes = await ipc.cmd
+            .get_settings()
+            .catch(ipc.handleError);
+
+        if (!settingsRes) return;
+
+        if (settingsRes.data.backend)
+            backend.set(settingsRes.data.backend as ipc.types.Backend);
+
+        watchSources.set(settingsRes.data.watch_sources === 'true');
+        prescale.set(settingsRes.data['hyprpaper.prescale'] === 'true');
+        themeExport.set(settingsRes.data['theme.export'] === 'true');
     });
 
+    async function changeBackend(value: ipc.types.Backend) {
+        const setSettingRes = await ipc.cmd
+            .set_setting({ key: 'backend', value })
+            .catch(ipc.handleError);
+
+        if (!setSettingRes) return;
+
+        backend.set(value);
+        toast.success(`Backend set to ${value}`);
+    }
+
+    async function changeWatchSources(value: boolean) {
+        const setSettingRes = await ipc.cmd
+            .set_setting({ key: 'watch_sources', value: String(value) })
+            .catch(ipc.handleError);
+
+        if (!setSettingRes) return;
+
+        watchSources.set(value);
+    }
+
+    async function changePrescale(value: boolean) {
+        const setSettingRes = await ipc.cmd
+            .set_setting({ key: 'hyprpaper.prescale', value: String(value) })
+            .catch(ipc.handleError);
+
+        if (!setSettingRes) return;
+
+        prescale.set(value);
+    }
+
+    async function changeThemeExport(value: boolean) {
+        const setSettingRes = await ipc.cmd
+            .set_setting({ key: 'theme.export', value: String(value) })
+            .catch(ipc.handleError);
+
+        if (!setSettingRes) return;
+
+        themeExport.set(value);
+    }
+
     async function addSource() {
         const directory = await open({
             directory: true,
@@ -45,28 +109,81 @@ export function Settings() {
             addWallpaperSourceRes.data!,
         ]);
 
-        const utilScanSource = await toast
-            .promise(
+        const utilScanSource = await ipc
+            .withScanProgress(() =>
                 ipc.cmd.scan_source({
                     sourceId: addWallpaperSourceRes.data.id,
                 }),
-                {
-                    loading: 'Scanning...',
-                    success: 'Scan complete',
-                    error: 'Scan failed',
-                },
             )
             .catch(ipc.handleError);
 
         if (!utilScanSource) return;
 
-        wallpapers.set([...wallpapers.get(), ...utilScanSource.data]);
+        await refreshWallpapers();
     }
 
     return (
         <Show when={showSettings.get()}>
             <div class='settings-container'>
                 <div class='settings'>
+                    <div class='settings-sources-list-header'>
+                        <span>Backend</span>
+                        <select
+                            value={backend.get()}
+                            onInput={(e) =>
+                                changeBackend(
+                                    (e.target as HTMLSelectElement)
+                                        .value as ipc.types.Backend,
+                                )
+                            }
+                            title='Select wallpaper backend'
+                        >
+                            <For each={backends}>
+                                {(x) => <option value={x}>{x}</option>}
+                            </For>
+                        </select>
+                    </div>
+                    <div class='settings-sources-list-header'>
+                        <span>Watch sources for changes</span>
+                        <input
+                            type='checkbox'
+                            checked={watchSources.get()}
+                            onChange={(e) =>
+                                changeWatchSources(
+                                    (e.target as HTMLInputElement).checked,
+                                )
+                            }
+                            title='Add and remove wallpapers as files change'
+                        />
+                    </div>
+                    <div class='settings-sources-list-header'>
+                        <span>Export a theme from the wallpaper</span>
+                        <input
+                            type='checkbox'
+                            checked={themeExport.get()}
+                            onChange={(e) =>
+                                changeThemeExport(
+                                    (e.target as HTMLInputElement).checked,
+                                )
+                            }
+                            title='Write color files when a wallpaper is set'
+                        />
+                    </div>
+                    <Show when={backend.get() === 'hyprpaper'}>
+                        <div class='settings-sources-list-header'>
+                            <span>Scale wallpapers to the screen size</span>
+                            <input
+                                type='checkbox'
+                                checked={prescale.get()}
+                                onChange={(e) =>
+                                    changePrescale(
+                                        (e.target as HTMLInputElement).checked,
+                                    )
+                                }
+                                title='Hand hyprpaper cached copies at the screen resolution, saves memory with large images'
+                            />
+                        </div>
+                    </Show>
                     <div class='settings-sources-list-header'>
                         <span>Sources list</span>
                         <button onClick={addSource} title='Add new source'>
@@ -82,7 +199,6 @@ export function Settings() {
                                         path={x.path}
                                         active={x.active}
                                         wallpaperSources={wallpaperSources}
-                                        setWallpapers={wallpapers.set}
                                     />
                                 )}
                             </For>
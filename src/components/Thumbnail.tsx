@@ -47,7 +47,7 @@ export function Thumbnail(props: Props) {
                 loading='lazy'
                 ref={imgRef}
                 onError={(e) => (e.currentTarget.src = fallbackImage)}
-                title={props.wallpaper.keywords}
+                title={props.wallpaper.caption ?? props.wallpaper.keywords}
             />
             <button class='thumbnail-favorite' onClick={handleFavoriteClick}>
                 <Switch>
@@ -1,25 +1,53 @@
 import { Thumbnail } from '@components';
 import * as ipc from '@ipc';
-import { For, Match, onMount, Switch } from 'solid-js';
+import { createVisibilityObserver } from '@solid-primitives/intersection-observer';
+import {
+    createEffect,
+    For,
+    Match,
+    onCleanup,
+    onMount,
+    Switch,
+} from 'solid-js';
 import { useGlobalContext } from '@/store';
 
 export function ThumbnailsList() {
     const {
-        filteredItems,
         selectedScreen,
         selectedMode,
         selectedWallpaper,
         activeWallpapers,
         searchQuery,
         wallpapers,
+        refreshWallpapers,
+        loadMoreWallpapers,
     } = useGlobalContext();
+    let sentinelRef: HTMLDivElement | undefined;
+
+    // starts loading the next page a bit before the end of the list is reached
+    const useVisibilityObserver = createVisibilityObserver({
+        rootMargin: '400px',
+    });
+
+    const sentinelVisible = useVisibilityObserver(() => sentinelRef);
+
+    // a page too short to push the sentinel out of view loads the next one
+    createEffect(() => {
+        wallpapers.get();
+        if (sentinelVisible()) loadMoreWallpapers();
+    });
 
     onMount(async () => {
-        const wallpapersRes = await ipc.cmd
-            .get_wallpapers()
+        const activewallpapersRes = await ipc.cmd
+            .get_active_wallpapers()
             .catch(ipc.handleError);
-        if (wallpapersRes) wallpapers.set(wallpapersRes.data);
+        if (!activewallpapersRes) return;
 
+        activeWallpapers.set(activewallpapersRes.data);
+    });
+
+    // rotations change wallpapers in the background
+    const unlistenActiveChanged = ipc.events.active_changed(async () => {
         const activewallpapersRes = await ipc.cmd
             .get_active_wallpapers()
             .catch(ipc.handleError);
@@ -28,6 +56,15 @@ export function ThumbnailsList() {
         activeWallpapers.set(activewallpapersRes.data);
     });
 
+    // the source watcher adds and removes wallpapers in the background
+    const unlistenWallpapersChanged =
+        ipc.events.wallpapers_changed(refreshWallpapers);
+
+    onCleanup(() => {
+        unlistenActiveChanged.then((unlisten) => unlisten());
+        unlistenWallpapersChanged.then((unlisten) => unlisten());
+    });
+
     async function handleThumbnailClick(id: string) {
         const lastActiveWallpaper = selectedWallpaper.get();
         selectedWallpaper.set(id);
@@ -58,7 +95,7 @@ export function ThumbnailsList() {
 
     return (
         <div class='thumbnails-list'>
-            <For each={filteredItems()}>
+            <For each={wallpapers.get()}>
                 {(x) => {
                     return (
                         <Thumbnail
@@ -77,16 +114,19 @@ export function ThumbnailsList() {
                     );
                 }}
             </For>
+            <div ref={sentinelRef} />
             <Switch>
                 <Match
-                    when={filteredItems().length === 0 && !searchQuery.get()}
+                    when={wallpapers.get().length === 0 && !searchQuery.get()}
                 >
                     <div>
                         No wallpapers found. Add a source in the settings.
                     </div>
                 </Match>
-                <Match when={filteredItems().length === 0 && searchQuery.get()}>
-                    <div>{filteredItems().length} wallpapers found</div>
+                <Match
+                    when={wallpapers.get().length === 0 && searchQuery.get()}
+                >
+                    <div>0 wallpapers found</div>
                 </Match>
             </Switch>
         </div>
@@ -1,5 +1,5 @@
 import * as ipc from '@ipc';
-import { createSignal, Setter } from 'solid-js';
+import { createSignal } from 'solid-js';
 import toast from 'solid-toast';
 import { RemoveIcon } from '@/icons';
 import { useGlobalContext } from '@/store';
@@ -10,12 +10,11 @@ interface Props {
     path: string;
     active: boolean;
     wallpaperSources: SignalObject<ipc.types.WallpaperSource[]>;
-    setWallpapers: Setter<ipc.types.Wallpaper[]>;
 }
 
 export function WallpaperSource(props: Props) {
     const [active, setActive] = createSignal(props.active);
-    const { wallpapers } = useGlobalContext();
+    const { refreshWallpapers } = useGlobalContext();
 
     async function handleCheckboxChange(v: boolean) {
         setActive(v);
@@ -28,12 +27,7 @@ export function WallpaperSource(props: Props) {
         toast.success(
             `Source is now ${updateWallpaperSourceActiveRes.data.active ? 'active' : 'inactive'}`,
         );
-        const wallpapersRes = await ipc.cmd
-            .get_wallpapers()
-            .catch(ipc.handleError);
-        if (!wallpapersRes) return;
-
-        props.setWallpapers(wallpapersRes.data);
+        await refreshWallpapers();
 
         const wallpaperSources = await ipc.cmd
             .get_wallpaper_sources()
@@ -55,15 +49,7 @@ export function WallpaperSource(props: Props) {
                 .get()
                 .filter((x) => x.id !== removeWallpaperSourceRes.data.id),
         );
-        wallpapers.set(
-            wallpapers
-                .get()
-                .filter(
-                    (x) =>
-                        x.wallpaper_source_id !==
-                        removeWallpaperSourceRes.data.id,
-                ),
-        );
+        await refreshWallpapers();
     }
 
     return (
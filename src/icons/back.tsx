@@ -0,0 +1,13 @@
+export function BackIcon() {
+    return (
+        <svg
+            xmlns='http://www.w3.org/2000/svg'
+            width='20'
+            height='20'
+            fill='var(--text-muted)'
+            viewBox='0 0 24 24'
+        >
+            <path d='M15.41,16.59L10.83,12l4.58-4.59L14,6l-6,6,6,6Z' />
+        </svg>
+    );
+}
@@ -0,0 +1,13 @@
+export function ForwardIcon() {
+    return (
+        <svg
+            xmlns='http://www.w3.org/2000/svg'
+            width='20'
+            height='20'
+            fill='var(--text-muted)'
+            viewBox='0 0 24 24'
+        >
+            <path d='M8.59,16.59L13.17,12,8.59,7.41,10,6l6,6-6,6Z' />
+        </svg>
+    );
+}
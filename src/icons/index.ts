@@ -1,4 +1,6 @@
 export * from './add';
+export * from './back';
+export * from './forward';
 export * from './full_heart';
 export * from './heart';
 export * from './random';
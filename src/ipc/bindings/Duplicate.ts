@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A file with the same content as `wallpaper_id`, which has the only row for it.
+ */
+export type Duplicate = { path: string, wallpaper_id: string, wallpaper_source_id: string, file_size: bigint | null, mtime: bigint | null, };
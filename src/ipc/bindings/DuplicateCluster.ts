@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Duplicate } from "./Duplicate";
+import type { Wallpaper } from "./Wallpaper";
+
+/**
+ * Wallpapers that look alike, best resolution first, and the exact copies of each.
+ */
+export type DuplicateCluster = { wallpapers: Array<Wallpaper>, copies: Array<Duplicate>, };
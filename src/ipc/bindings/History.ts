@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type History = { id: number, screen: string, wallpaper_id: string, mode: string, origin: string, created_at: bigint, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Rotation = { screen: string, interval_secs: number, wallpaper_source_id: string | null, favorites_only: boolean, shuffle: boolean, mode: string, enabled: boolean, last_changed_at: bigint, collection_id: string | null, };
@@ -0,0 +1,19 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ScanStage } from "./ScanStage";
+
+/**
+ * Counters are totals of the whole run, across sources for `cmd_scan_all_sources`.
+ */
+export type ScanProgress = { stage: ScanStage, 
+/**
+ * Source currently being walked
+ */
+source_id: string | null, 
+/**
+ * Image files found
+ */
+discovered: number, 
+/**
+ * New or changed files, unchanged ones aren't hashed again
+ */
+hashed: number, inserted: number, removed: number, thumbnails_total: number, thumbnails_done: number, errors: number, last_error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of one screen in a batch, `error` is `None` on success.
+ */
+export type ScreenResult = { screen: string, error: string | null, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A tag and the number of wallpapers that have it.
+ */
+export type TagCount = { name: string, count: bigint, };
@@ -1,3 +1,15 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type Wallpaper = { id: string, is_favorite: boolean, signature: string, path: string, thumbnail_path: string, resolution: string | null, wallpaper_source_id: string, keywords: string | null, };
+export type Wallpaper = { id: string, is_favorite: boolean, signature: string, path: string, thumbnail_path: string, resolution: string | null, wallpaper_source_id: string, keywords: string | null, width: number | null, height: number | null, aspect_ratio: number | null, file_size: bigint | null, mtime: bigint | null, 
+/**
+ * Comma separated `#rrggbb` colors, most dominant first
+ */
+palette: string | null, 
+/**
+ * From the metadata file of the source
+ */
+caption: string | null, 
+/**
+ * From the metadata file of the source
+ */
+category: string | null, };
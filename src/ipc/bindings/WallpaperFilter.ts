@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Optional constraints for `cmd_get_wallpapers`, unset fields don't filter. Wallpapers
+ * scanned before dimensions were stored only match an empty filter.
+ */
+export type WallpaperFilter = { min_width?: number, max_width?: number, min_height?: number, max_height?: number, 
+/**
+ * e.g. `2.3` for ultrawide
+ */
+min_aspect_ratio?: number, max_aspect_ratio?: number, 
+/**
+ * Bytes
+ */
+min_file_size?: bigint, 
+/**
+ * Bytes
+ */
+max_file_size?: bigint, 
+/**
+ * Tag query, e.g. `nature AND NOT (snow OR winter)`
+ */
+tags?: string, };
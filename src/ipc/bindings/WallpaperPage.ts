@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Wallpaper } from "./Wallpaper";
+
+/**
+ * Result of `cmd_search_wallpapers`, `next_cursor` is `None` on the last page.
+ */
+export type WallpaperPage = { wallpapers: Array<Wallpaper>, 
+/**
+ * Matches of the search across all pages
+ */
+total: bigint, next_cursor: string | null, };
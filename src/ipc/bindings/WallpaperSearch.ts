@@ -0,0 +1,49 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Orientation } from "./Orientation";
+import type { SearchSort } from "./SearchSort";
+
+/**
+ * One page of `cmd_search_wallpapers`, unset fields don't filter. Pass the `next_cursor` of
+ * a page, with everything else unchanged, to get the next one.
+ */
+export type WallpaperSearch = { 
+/**
+ * Words found in the keywords, file name, caption or category, each as a prefix
+ */
+text?: string, source_id?: string, 
+/**
+ * Only favorites
+ */
+favorites: boolean, 
+/**
+ * e.g. `3840x2160`
+ */
+resolution?: string, orientation?: Orientation, 
+/**
+ * Category from the metadata file, the whole of it
+ */
+category?: string, sort: SearchSort, descending: boolean, 
+/**
+ * Favorites before the other wallpapers, each sorted by `sort`
+ */
+favorites_first: boolean, cursor?: string, 
+/**
+ * Wallpapers per page, 100 by default and at most 1000
+ */
+limit?: number, min_width?: number, max_width?: number, min_height?: number, max_height?: number, 
+/**
+ * e.g. `2.3` for ultrawide
+ */
+min_aspect_ratio?: number, max_aspect_ratio?: number, 
+/**
+ * Bytes
+ */
+min_file_size?: bigint, 
+/**
+ * Bytes
+ */
+max_file_size?: bigint, 
+/**
+ * Tag query, e.g. `nature AND NOT (snow OR winter)`
+ */
+tags?: string, };
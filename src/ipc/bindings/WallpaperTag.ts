@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A tag of one wallpaper, `origin` is `filename`, `metadata` or `user`.
+ */
+export type WallpaperTag = { name: string, origin: string, };
@@ -1,5 +1,22 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
 export * from "./Active.ts";
+export * from "./Collection.ts";
+export * from "./Duplicate.ts";
+export * from "./DuplicateCluster.ts";
+export * from "./History.ts";
+export * from "./MonitorEvent.ts";
+export * from "./Orientation.ts";
+export * from "./Rotation.ts";
+export * from "./ScanProgress.ts";
+export * from "./ScanStage.ts";
+export * from "./ScreenAssignment.ts";
+export * from "./ScreenResult.ts";
+export * from "./SearchSort.ts";
+export * from "./TagCount.ts";
 export * from "./Wallpaper.ts";
-export * from "./WallpaperSource.ts";
\ No newline at end of file
+export * from "./WallpaperFilter.ts";
+export * from "./WallpaperPage.ts";
+export * from "./WallpaperSearch.ts";
+export * from "./WallpaperSource.ts";
+export * from "./WallpaperTag.ts";
\ No newline at end of file
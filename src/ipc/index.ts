@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import toast from 'solid-toast';
 import * as types from './types.ts';
 
@@ -11,13 +12,67 @@ export function handleError(err: string) {
 
 type CmdReturn<T> = Promise<types.Response<T>>;
 
+export const events = {
+    monitors_changed: (handler: (payload: types.MonitorEvent) => void) =>
+        listen<types.MonitorEvent>('monitors-changed', (e) =>
+            handler(e.payload),
+        ),
+    active_changed: (handler: () => void) =>
+        listen('active-changed', () => handler()),
+    wallpapers_changed: (handler: () => void) =>
+        listen('wallpapers-changed', () => handler()),
+    scan_progress: (handler: (payload: types.ScanProgress) => void) =>
+        listen<types.ScanProgress>('scan-progress', (e) =>
+            handler(e.payload),
+        ),
+};
+
+function describeScan(progress: types.ScanProgress) {
+    const errors = progress.errors ? `, ${progress.errors} errors` : '';
+
+    if (progress.stage === 'thumbnails') {
+        return `Creating thumbnails ${progress.thumbnails_done}/${progress.thumbnails_total}${errors}`;
+    }
+
+    return `Scanning... ${progress.discovered} found, ${progress.hashed} hashed${errors}`;
+}
+
+// Shows the progress events of a scan in a toast, the listener is
+// registered before the scan starts so no event is missed
+export async function withScanProgress<T>(scan: () => CmdReturn<T>) {
+    const id = toast.loading('Scanning...');
+    let cancelled = false;
+
+    const unlisten = await events.scan_progress((progress) => {
+        cancelled = progress.stage === 'cancelled';
+        toast.loading(describeScan(progress), { id });
+    });
+
+    try {
+        const res = await scan();
+        toast.success(cancelled ? 'Scan cancelled' : 'Scan complete', { id });
+
+        return res;
+    } catch (err) {
+        toast.error('Scan failed', { id });
+        throw err;
+    } finally {
+        unlisten();
+    }
+}
+
 export const cmd = {
     set_wallpaper: (args: {
         screen: string;
         wallpaperId?: string;
+        collectionId?: string;
         mode: types.Mode;
         isTemporary: boolean;
     }): CmdReturn<types.Active> => invoke('cmd_set_wallpaper', args),
+    set_wallpapers: (args: {
+        assignments: types.ScreenAssignment[];
+        isTemporary: boolean;
+    }): CmdReturn<types.ScreenResult[]> => invoke('cmd_set_wallpapers', args),
     add_wallpaper_source: (args: {
         path: string;
     }): CmdReturn<types.WallpaperSource> =>
@@ -25,8 +80,14 @@ export const cmd = {
     get_screens: (): CmdReturn<string[]> => invoke('cmd_get_screens'),
     get_wallpaper_sources: (): CmdReturn<types.WallpaperSource[]> =>
         invoke('cmd_get_wallpaper_sources'),
-    get_wallpapers: (): CmdReturn<types.Wallpaper[]> =>
-        invoke('cmd_get_wallpapers'),
+    get_wallpapers: (args?: {
+        filter?: types.WallpaperFilter;
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_get_wallpapers', args),
+    // pass the next_cursor of a page with an otherwise unchanged search
+    search_wallpapers: (args?: {
+        search?: types.WallpaperSearch;
+    }): CmdReturn<types.WallpaperPage> =>
+        invoke('cmd_search_wallpapers', args),
     get_active_wallpapers: (): CmdReturn<types.Active[]> =>
         invoke('cmd_get_active_wallpapers'),
     remove_wallpaper_source: (args: {
@@ -47,6 +108,81 @@ export const cmd = {
         invoke('cmd_scan_source', args),
     scan_all_sources: (): CmdReturn<types.Wallpaper[]> =>
         invoke('cmd_scan_all_sources'),
+    cancel_scan: (): CmdReturn<boolean> => invoke('cmd_cancel_scan'),
     restore_wallpapers: (): CmdReturn<boolean> =>
         invoke('cmd_restore_wallpapers'),
+    get_settings: (): CmdReturn<Record<string, string>> =>
+        invoke('cmd_get_settings'),
+    set_setting: (args: { key: string; value: string }): CmdReturn<string> =>
+        invoke('cmd_set_setting', args),
+    get_rotations: (): CmdReturn<types.Rotation[]> =>
+        invoke('cmd_get_rotations'),
+    set_rotation: (args: {
+        screen: string;
+        intervalSecs: number;
+        wallpaperSourceId?: string;
+        collectionId?: string;
+        favoritesOnly: boolean;
+        shuffle: boolean;
+        mode: types.Mode;
+        enabled: boolean;
+    }): CmdReturn<types.Rotation> => invoke('cmd_set_rotation', args),
+    advance_rotation: (args: { screen: string }): CmdReturn<types.Wallpaper> =>
+        invoke('cmd_advance_rotation', args),
+    remove_rotation: (args: { screen: string }): CmdReturn<types.Rotation> =>
+        invoke('cmd_remove_rotation', args),
+    get_history: (args: {
+        screen?: string;
+        limit: number;
+    }): CmdReturn<types.History[]> => invoke('cmd_get_history', args),
+    history_back: (args: { screen: string }): CmdReturn<types.Wallpaper[]> =>
+        invoke('cmd_history_back', args),
+    history_forward: (args: {
+        screen: string;
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_history_forward', args),
+    get_duplicates: (args?: {
+        maxDistance?: number;
+    }): CmdReturn<types.DuplicateCluster[]> =>
+        invoke('cmd_get_duplicates', args),
+    get_collections: (): CmdReturn<types.Collection[]> =>
+        invoke('cmd_get_collections'),
+    get_collection_wallpapers: (args: {
+        collectionId: string;
+    }): CmdReturn<types.Wallpaper[]> =>
+        invoke('cmd_get_collection_wallpapers', args),
+    create_collection: (args: { name: string }): CmdReturn<types.Collection> =>
+        invoke('cmd_create_collection', args),
+    rename_collection: (args: {
+        id: string;
+        name: string;
+    }): CmdReturn<types.Collection> => invoke('cmd_rename_collection', args),
+    remove_collection: (args: { id: string }): CmdReturn<types.Collection> =>
+        invoke('cmd_remove_collection', args),
+    add_to_collection: (args: {
+        collectionId: string;
+        wallpaperIds: string[];
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_add_to_collection', args),
+    remove_from_collection: (args: {
+        collectionId: string;
+        wallpaperIds: string[];
+    }): CmdReturn<types.Wallpaper[]> =>
+        invoke('cmd_remove_from_collection', args),
+    // wallpaperIds lists every wallpaper of the collection in the new order
+    reorder_collection: (args: {
+        collectionId: string;
+        wallpaperIds: string[];
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_reorder_collection', args),
+    get_tags: (): CmdReturn<types.TagCount[]> => invoke('cmd_get_tags'),
+    get_wallpaper_tags: (args: {
+        wallpaperId: string;
+    }): CmdReturn<types.WallpaperTag[]> =>
+        invoke('cmd_get_wallpaper_tags', args),
+    add_tags: (args: {
+        wallpaperIds: string[];
+        tags: string[];
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_add_tags', args),
+    remove_tags: (args: {
+        wallpaperIds: string[];
+        tags: string[];
+    }): CmdReturn<types.Wallpaper[]> => invoke('cmd_remove_tags', args),
 };
@@ -1,6 +1,17 @@
 export * from './bindings';
 
-export type Mode = 'default' | 'contain' | 'tile';
+export type Mode =
+    | 'default'
+    | 'cover'
+    | 'contain'
+    | `contain:#${string}`
+    | 'contain:blur'
+    | 'stretch'
+    | 'center'
+    | 'tile'
+    | 'span';
+
+export type Backend = 'hyprpaper' | 'swww' | 'swaybg' | 'wpaperd';
 
 export type Error = string;
 
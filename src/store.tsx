@@ -1,23 +1,30 @@
 import * as ipc from '@ipc';
 import { createSignalObject, SignalObject } from '@utils';
 import {
-    Accessor,
     createContext,
-    createMemo,
+    createEffect,
     JSXElement,
+    on,
     useContext,
 } from 'solid-js';
 
+// wallpapers loaded per request, more are loaded while scrolling
+const PAGE_SIZE = 100;
+
 interface ContextProps {
     selectedScreen: SignalObject<string>;
     showSettings: SignalObject<boolean>;
     searchQuery: SignalObject<string>;
     selectedMode: SignalObject<ipc.types.Mode>;
     debouncedSearchQuery: SignalObject<string>;
+    // the pages of the search loaded so far
     wallpapers: SignalObject<ipc.types.Wallpaper[]>;
+    // matches of the search, loaded or not
+    totalWallpapers: SignalObject<number>;
     selectedWallpaper: SignalObject<string>;
     activeWallpapers: SignalObject<ipc.types.Active[]>;
-    filteredItems: Accessor<ipc.types.Wallpaper[]>;
+    refreshWallpapers: () => Promise<void>;
+    loadMoreWallpapers: () => Promise<void>;
 }
 
 const GlobalContext = createContext<ContextProps>();
@@ -32,24 +39,61 @@ export function GlobalContextProvider(props: { children: JSXElement }) {
     const selectedWallpaper = createSignalObject<string>(''); // wallpaper_id
     const activeWallpapers = createSignalObject<ipc.types.Active[]>([]);
 
-    const filteredItems = createMemo(() => {
-        const query = debouncedSearchQuery.get().toLowerCase();
-        const sortedList = wallpapers
-            .get()
-            .sort((a: ipc.types.Wallpaper, b: ipc.types.Wallpaper) => {
-                if (a.is_favorite && !b.is_favorite) return -1;
-                if (!a.is_favorite && b.is_favorite) return 1;
-                return 0;
-            });
-
-        if (!query) {
-            return sortedList;
-        }
-
-        return sortedList.filter((item) =>
-            item.keywords.toLowerCase().includes(query),
+    const totalWallpapers = createSignalObject(0);
+    const nextCursor = createSignalObject<string | null>(null);
+    // pages of an outdated search are dropped
+    let searchId = 0;
+    let loadingMore = false;
+
+    function search(cursor: string | null): ipc.types.WallpaperSearch {
+        return {
+            text: debouncedSearchQuery.get(),
+            favorites: false,
+            sort: 'path',
+            descending: false,
+            favorites_first: true,
+            cursor: cursor ?? undefined,
+            limit: PAGE_SIZE,
+        };
+    }
+
+    function setPage(page: ipc.types.WallpaperPage, append: boolean) {
+        wallpapers.set(
+            append ? [...wallpapers.get(), ...page.wallpapers] : page.wallpapers,
         );
-    });
+        totalWallpapers.set(Number(page.total));
+        nextCursor.set(page.next_cursor);
+    }
+
+    // loads the first page again
+    async function refreshWallpapers() {
+        const id = ++searchId;
+        const pageRes = await ipc.cmd
+            .search_wallpapers({ search: search(null) })
+            .catch(ipc.handleError);
+
+        if (!pageRes || id !== searchId) return;
+
+        setPage(pageRes.data, false);
+    }
+
+    async function loadMoreWallpapers() {
+        const cursor = nextCursor.get();
+        if (!cursor || loadingMore) return;
+
+        const id = searchId;
+        loadingMore = true;
+        const pageRes = await ipc.cmd
+            .search_wallpapers({ search: search(cursor) })
+            .catch(ipc.handleError);
+        loadingMore = false;
+
+        if (!pageRes || id !== searchId) return;
+
+        setPage(pageRes.data, true);
+    }
+
+    createEffect(on(debouncedSearchQuery.get, refreshWallpapers));
 
     return (
         <GlobalContext.Provider
@@ -60,9 +104,11 @@ export function GlobalContextProvider(props: { children: JSXElement }) {
                 selectedMode,
                 debouncedSearchQuery,
                 wallpapers,
+                totalWallpapers,
                 selectedWallpaper,
                 activeWallpapers,
-                filteredItems,
+                refreshWallpapers,
+                loadMoreWallpapers,
             }}
         >
             {props.children}